@@ -0,0 +1,236 @@
+use std::collections::hash_map::DefaultHasher;
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+use proto_mav_build::{BuildConfig, CONFIG_ENV};
+
+/// File in OUT_DIR holding the input hash of the last generation.
+const HASH_FILE: &str = "proto-mav-gen.hash";
+/// File in the output directory holding the hash of the generated sources
+/// and the settings they were generated with, checked instead of generating
+/// in vendored mode.
+const SOURCES_HASH_FILE: &str = "sources.hash";
+
+/// The XML files of the definition directories, sorted by path.
+pub fn definition_files(definitions_dirs: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = vec![];
+    for dir in definitions_dirs {
+        for entry in fs::read_dir(dir).expect("could not read definitions directory") {
+            let path = entry.expect("could not read directory entry").path();
+            if path.extension().is_some_and(|ext| ext == "xml") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Hash of everything the generated code depends on: the definitions, the
+/// build config, the enabled features and the generator itself.
+pub fn input_hash(src_dir: &Path, definitions_dirs: &[PathBuf]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for path in definition_files(definitions_dirs) {
+        path.hash(&mut hasher);
+        fs::read(&path).unwrap_or_default().hash(&mut hasher);
+    }
+    fs::read(BuildConfig::path(src_dir))
+        .unwrap_or_default()
+        .hash(&mut hasher);
+    let mut vars = env::vars()
+        .filter(|(key, _)| key.starts_with("CARGO_FEATURE_") || key.starts_with("PROTO_MAV_"))
+        .collect::<Vec<(String, String)>>();
+    vars.sort();
+    vars.hash(&mut hasher);
+    // a changed generator is a new build script binary
+    if let Ok(exe) = env::current_exe() {
+        fs::read(exe).unwrap_or_default().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn hash_path() -> Option<PathBuf> {
+    env::var_os("OUT_DIR").map(|dir| Path::new(&dir).join(HASH_FILE))
+}
+
+/// The input hash and the sources hash file of `out_dir`, as stored in
+/// OUT_DIR after a generation.
+fn stored_hashes(out_dir: &Path, hash: u64) -> String {
+    let sources = fs::read_to_string(out_dir.join(SOURCES_HASH_FILE)).unwrap_or_default();
+    format!("{}\n{}", hash, sources)
+}
+
+/// True when the output in `out_dir` was generated from the same inputs.
+/// OUT_DIR differs per feature set while `out_dir` is shared, so the sources
+/// hash file (with the features it was generated with) has to be unchanged
+/// as well, another build may have regenerated the output.
+pub fn is_fresh(out_dir: &Path, hash: u64) -> bool {
+    match hash_path().map(fs::read_to_string) {
+        Some(Ok(stored)) => stored == stored_hashes(out_dir, hash),
+        _ => false,
+    }
+}
+
+/// Cargo does not rerun the build script when switching back to a feature
+/// set it has built before, so it has to watch the shared output as well.
+/// Regenerating touches the file, the next build reruns once and finds the
+/// output fresh.
+pub fn watch_output(out_dir: &Path) {
+    println!(
+        "cargo:rerun-if-changed={}",
+        out_dir.join(SOURCES_HASH_FILE).display()
+    );
+}
+
+/// Parts of the generated crate that end up in the build: the manifest,
+/// the sources and the files they `include_str!`/`include_bytes!` (protos,
+/// schemas, the descriptor set) or ship with (the C header).
+const CRATE_PATHS: &[&str] = &["Cargo.toml", "src", "protos", "include"];
+
+/// The files at or below `path`, sorted by path.
+fn files_below(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+    } else if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            files_below(&entry.path(), files);
+        }
+    }
+    files.sort();
+}
+
+/// 64 bit FNV-1a, unlike `DefaultHasher` the same with every compiler, the
+/// hashes are shipped with the sources.
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Fnv {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Hash of the generated crate in `out_dir`, see `CRATE_PATHS`.
+fn sources_hash(out_dir: &Path) -> u64 {
+    let mut files = vec![];
+    for path in CRATE_PATHS {
+        files_below(&out_dir.join(path), &mut files);
+    }
+    let mut hash = Fnv::new();
+    for path in files {
+        let name = path.strip_prefix(out_dir).unwrap().to_string_lossy();
+        hash.update(name.replace('\\', "/").as_bytes());
+        let contents = fs::read(&path).unwrap_or_default();
+        hash.update(&(contents.len() as u64).to_le_bytes());
+        hash.update(&contents);
+    }
+    hash.0
+}
+
+/// The enabled cargo features (dialects as well as serde, ffi, ...), lower
+/// case and sorted.
+fn enabled_features() -> String {
+    let mut features = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|feature| feature.to_lowercase())
+        })
+        .collect::<Vec<String>>();
+    features.sort();
+    features.join(",")
+}
+
+/// Hash of the build config file, 0 without one.
+fn config_hash(src_dir: &Path) -> u64 {
+    match fs::read(BuildConfig::path(src_dir)) {
+        Ok(contents) => {
+            let mut hash = Fnv::new();
+            hash.update(&contents);
+            hash.0
+        }
+        Err(_) => 0,
+    }
+}
+
+/// `key value` lines of the sources hash file.
+fn vendored_settings(out_dir: &Path, src_dir: &Path) -> Vec<(&'static str, String)> {
+    vec![
+        ("sources", sources_hash(out_dir).to_string()),
+        ("features", enabled_features()),
+        ("config", config_hash(src_dir).to_string()),
+    ]
+}
+
+/// Record the hash of freshly generated sources, the features and the
+/// build config for the vendored mode.
+pub fn store_sources_hash(out_dir: &Path, src_dir: &Path) {
+    let contents = vendored_settings(out_dir, src_dir)
+        .into_iter()
+        .map(|(key, value)| format!("{} {}\n", key, value))
+        .collect::<String>();
+    fs::write(out_dir.join(SOURCES_HASH_FILE), contents).unwrap();
+}
+
+/// Vendored mode, the sources in `out_dir` are used as they are.  Panics if
+/// they were never generated, have been edited since, or were generated
+/// with other features or another build config than this build has.
+pub fn verify_vendored(out_dir: &Path, src_dir: &Path) {
+    let hash_path = out_dir.join(SOURCES_HASH_FILE);
+    let stored = fs::read_to_string(&hash_path).unwrap_or_else(|_| {
+        panic!(
+            "no vendored sources in {}, generate them with a normal build first",
+            out_dir.display()
+        )
+    });
+    let stored = stored
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .collect::<Vec<(&str, &str)>>();
+    for (key, value) in vendored_settings(out_dir, src_dir) {
+        match stored.iter().find(|(stored_key, _)| *stored_key == key) {
+            Some((_, stored_value)) if *stored_value == value => (),
+            Some((_, stored_value)) if key == "features" => panic!(
+                "the vendored sources in {} were generated with the features [{}], this build has [{}], regenerate them with a normal build",
+                out_dir.display(),
+                stored_value,
+                value
+            ),
+            _ => panic!(
+                "the vendored sources in {} do not match their {} in {}, regenerate them with a normal build",
+                out_dir.display(),
+                key,
+                hash_path.display()
+            ),
+        }
+    }
+    for path in CRATE_PATHS {
+        println!("cargo:rerun-if-changed={}", out_dir.join(path).display());
+    }
+    println!("cargo:rerun-if-changed={}", hash_path.display());
+    println!("cargo:rerun-if-env-changed={}", CONFIG_ENV);
+    println!(
+        "cargo:rerun-if-changed={}",
+        BuildConfig::path(src_dir).display()
+    );
+}
+
+/// Remember the inputs of a successful generation into `out_dir`, after
+/// `store_sources_hash`.
+pub fn store(out_dir: &Path, hash: u64) {
+    if let Some(path) = hash_path() {
+        if let Err(error) = fs::write(&path, stored_hashes(out_dir, hash)) {
+            println!(
+                "cargo:warning=could not write {}: {}",
+                path.display(),
+                error
+            );
+        }
+    }
+}
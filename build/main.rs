@@ -1,38 +1,53 @@
-#![recursion_limit = "256"]
-#[macro_use]
-extern crate quote;
+mod cache;
 
-extern crate xml;
-
-mod binder;
-mod mavlink;
-mod parser;
-mod proto;
-mod util;
-
-use crate::util::to_module_name;
-use std::collections::HashMap;
 use std::env;
 use std::fs::{read_dir, File};
 use std::io::Write;
 use std::path::Path;
 use std::process::Command;
 
-pub fn main() {
-    let src_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+use proto_mav_build::BuildConfig;
+
+/// Directory with the MAVLink XML files to use instead of the bundled ones.
+const DEFINITIONS_ENV: &str = "PROTO_MAV_DEFINITIONS_DIR";
+/// Extra directories (a path list) with dialects to generate, for example
+/// private dialects including common.xml.
+const EXTRA_XML_DIRS_ENV: &str = "PROTO_MAV_EXTRA_XML_DIRS";
+/// Use the already generated sources as they are (after checking their
+/// hash), without git, XML parsing or protoc.  Always on for docs.rs.
+const VENDORED_ENV: &str = "PROTO_MAV_VENDORED";
+
+/// Whether the cargo feature of a bundled dialect is enabled, the features
+/// are the lower case file names (`ASLUAV.xml` is `asluav`).
+fn dialect_enabled(xml_path: &Path) -> bool {
+    let feature = xml_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_uppercase().replace('-', "_"))
+        .unwrap_or_default();
+    env::var_os(format!("CARGO_FEATURE_{}", feature)).is_some()
+}
 
-    // find & apply patches to XML definitions to avoid crashes
+/// Apply build/patches to the MAVLink definitions.  Only done for a git
+/// checkout of the definitions, a vendored copy is used as is.
+fn apply_patches(src_dir: &Path, mavlink_dir: &Path) {
+    if !mavlink_dir.join(".git").exists() {
+        return;
+    }
     let mut patch_dir = src_dir.to_path_buf();
     patch_dir.push("build/patches");
-    let mut mavlink_dir = src_dir.to_path_buf();
-    mavlink_dir.push("mavlink");
+    if patch_dir.is_dir() {
+        println!("cargo:rerun-if-changed={}", patch_dir.display());
+    }
 
     if let Ok(dir) = read_dir(patch_dir) {
-        for entry in dir.flatten() {
+        // patches can depend on each other, apply them in name order
+        let mut entries = dir.flatten().collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
             match Command::new("git")
                 .arg("apply")
                 .arg(entry.path().as_os_str())
-                .current_dir(&mavlink_dir)
+                .current_dir(mavlink_dir)
                 .status()
             {
                 Ok(_) => (),
@@ -40,111 +55,86 @@ pub fn main() {
             }
         }
     }
+}
 
-    let mut definitions_dir = src_dir.to_path_buf();
-    definitions_dir.push("mavlink/message_definitions/v1.0");
-
+pub fn main() {
+    let src_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
     let out_dir = format!("{}/proto-mav-gen", src_dir.display());
-    let mav_out = format!("{}/proto-mav-gen/src/mavlink", src_dir.display());
-    if std::fs::create_dir_all(&mav_out).is_err() {} // Do not care if this exists.
-    let proto_out = format!("{}/proto-mav-gen/src/proto", src_dir.display());
-    if std::fs::create_dir(&proto_out).is_err() {} // Do not care if this exists.
-    let protobufs_out = format!("{}/proto-mav-gen/protos", src_dir.display());
-    if std::fs::create_dir(&protobufs_out).is_err() {} // Do not care if this exists.
-
-    let mut modules = vec![];
-    let mut modules_map: HashMap<String, parser::MavProfile> = HashMap::new();
 
-    for entry in read_dir(&definitions_dir).expect("could not read definitions directory") {
-        let entry = entry.expect("could not read directory entry");
-
-        let definition_file = entry.file_name();
-        let module_name = to_module_name(&definition_file);
-
-        modules.push(module_name);
-
-        parser::generate(
-            &definitions_dir,
-            &definition_file,
-            &out_dir,
-            &mut modules_map,
-        );
+    println!("cargo:rerun-if-env-changed={}", VENDORED_ENV);
+    if env::var_os(VENDORED_ENV).is_some() || env::var_os("DOCS_RS").is_some() {
+        cache::verify_vendored(Path::new(&out_dir), src_dir);
+        return;
     }
 
-    // output mod.rs for src
-    {
-        let out_dir = Path::new(&out_dir).join("src");
-        let dest_path = Path::new(&out_dir).join("lib.rs");
-        {
-            let mut outf = File::create(&dest_path).unwrap();
-
-            let src_modules = vec!["mavlink".to_string(), "proto".to_string()];
-            // generate code
-            binder::generate_bare(&src_modules, &mut outf);
-        }
+    let mut build_config = BuildConfig::load(src_dir);
+    // HEARTBEAT2 of localtest is past the frame limit on purpose
+    build_config.oversized_dialects.push("localtest".into());
+    // tests/u8_bytes_tests.rs only builds against a crate generated this way
+    println!("cargo:rustc-check-cfg=cfg(proto_mav_u8_bytes)");
+    if build_config.proto_u8_bytes {
+        println!("cargo:rustc-cfg=proto_mav_u8_bytes");
+    }
 
-        // format code
-        match Command::new("rustfmt")
-            .arg(dest_path.as_os_str())
-            .current_dir(&out_dir)
-            .status()
-        {
-            Ok(_) => (),
-            Err(error) => eprintln!("{}", error),
+    println!("cargo:rerun-if-env-changed={}", DEFINITIONS_ENV);
+    let definitions_dir = match env::var_os(DEFINITIONS_ENV) {
+        // relative paths are relative to the crate root
+        Some(dir) => src_dir.join(dir),
+        None => {
+            let mut mavlink_dir = src_dir.to_path_buf();
+            mavlink_dir.push("mavlink");
+            apply_patches(src_dir, &mavlink_dir);
+            mavlink_dir.join("message_definitions/v1.0")
         }
+    };
+    if !definitions_dir.is_dir() {
+        panic!(
+            "MAVLink definitions not found in {}, set {} to a directory with the XML files",
+            definitions_dir.display(),
+            DEFINITIONS_ENV
+        );
     }
-
-    // output mod.rs for mavlink
-    {
-        let out_dir = Path::new(&out_dir).join("src").join("mavlink");
-        let dest_path = Path::new(&out_dir).join("mod.rs");
-        {
-            let mut outf = File::create(&dest_path).unwrap();
-
-            // generate code
-            binder::generate(&modules, &mut outf);
+    // picks up added and removed dialects
+    println!("cargo:rerun-if-changed={}", definitions_dir.display());
+
+    println!("cargo:rerun-if-env-changed={}", EXTRA_XML_DIRS_ENV);
+    let mut definitions_dirs = vec![definitions_dir.clone()];
+    if let Some(dirs) = env::var_os(EXTRA_XML_DIRS_ENV) {
+        for dir in env::split_paths(&dirs) {
+            let dir = src_dir.join(dir);
+            if !dir.is_dir() {
+                panic!(
+                    "{}: {} is not a directory",
+                    EXTRA_XML_DIRS_ENV,
+                    dir.display()
+                );
+            }
+            // picks up added and removed dialects
+            println!("cargo:rerun-if-changed={}", dir.display());
+            definitions_dirs.push(dir);
         }
+    }
 
-        // format code
-        match Command::new("rustfmt")
-            .arg(dest_path.as_os_str())
-            .current_dir(&out_dir)
-            .status()
-        {
-            Ok(_) => (),
-            Err(error) => eprintln!("{}", error),
+    let input_hash = cache::input_hash(src_dir, &definitions_dirs);
+    cache::watch_output(Path::new(&out_dir));
+    if cache::is_fresh(Path::new(&out_dir), input_hash) {
+        for path in cache::definition_files(&definitions_dirs) {
+            println!("cargo:rerun-if-changed={}", path.display());
         }
+        return;
     }
 
+    // only the dialects with their feature enabled (and their includes),
+    // extra dialects have no feature and are always generated
+    let selected = |xml: &Path| !xml.starts_with(&definitions_dir) || dialect_enabled(xml);
+    if !cache::definition_files(&definitions_dirs)
+        .iter()
+        .any(|xml| selected(xml))
     {
-        let dest_path = Path::new(&protobufs_out).join("mav.proto");
-        let mut outf = File::create(&dest_path).unwrap();
-        let opts = r#"
-syntax = "proto3";
-
-package mav;
-
-import "google/protobuf/descriptor.proto";
-
-message MavFieldOptions {
-  optional string type = 1;
-  optional string enum = 2;
-  optional string display = 3;
-}
-
-message MavMesOptions {
-  optional int32 id = 1;
-}
-
-extend google.protobuf.FieldOptions {
-  optional MavFieldOptions opts = 60066;
-}
-extend google.protobuf.MessageOptions {
-  optional MavMesOptions message = 60066;
-}
-"#;
-        outf.write_all(opts.as_bytes()).unwrap();
+        panic!("no dialect selected, enable at least one dialect feature (e.g. \"common\")");
     }
+    proto_mav_build::generate_dirs_filtered(&definitions_dirs, &out_dir, &build_config, selected);
+
     {
         let dest_path = Path::new(&out_dir).join("README.md");
         let mut outf = File::create(&dest_path).unwrap();
@@ -174,41 +164,18 @@ num-traits = { version = "0.2", default-features = false }
 num-derive = "0.3.2"
 bitflags = "1.2.1"
 proto_mav_comm = { git="https://github.com/eucleo/proto-mav-comm.git" }
-serde = { version = "1" }
+serde = { version = "1", features = ["derive"] }
 prost = "0.9"
 "#;
         outf.write_all(opts.as_bytes()).unwrap();
-    }
-    let mut protos = Vec::new();
-    for module in &modules {
-        protos.push(format!("{}/{}.proto", protobufs_out, module));
-    }
-    prost_build::Config::new()
-        .out_dir(proto_out)
-        //        .compile_well_known_types()
-        .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
-        .compile_protos(&protos, &[protobufs_out])
-        .unwrap();
-
-    // output mod.rs for proto
-    {
-        let out_dir = Path::new(&out_dir).join("src").join("proto");
-        let dest_path = Path::new(&out_dir).join("mod.rs");
-        {
-            let mut outf = File::create(&dest_path).unwrap();
-
-            // generate code
-            binder::generate(&modules, &mut outf);
+        if cfg!(feature = "grpc") {
+            outf.write_all(b"tonic = \"0.6\"\n").unwrap();
         }
-
-        // format code
-        match Command::new("rustfmt")
-            .arg(dest_path.as_os_str())
-            .current_dir(&out_dir)
-            .status()
-        {
-            Ok(_) => (),
-            Err(error) => eprintln!("{}", error),
+        if cfg!(feature = "ffi") {
+            outf.write_all(b"\n[lib]\ncrate-type = [\"rlib\", \"staticlib\", \"cdylib\"]\n")
+                .unwrap();
         }
     }
+    cache::store_sources_hash(Path::new(&out_dir), src_dir);
+    cache::store(Path::new(&out_dir), input_hash);
 }
@@ -0,0 +1,1130 @@
+use proc_macro2::TokenStream;
+use std::io::Write;
+
+use crate::util::ident;
+
+pub fn generate<W: Write>(modules: &[String], out: &mut W) {
+    let modules_tokens = modules.iter().map(|module| {
+        let module_ident = ident(module);
+
+        // the generated code itself uses the deprecated messages and enums
+        quote! {
+            #[allow(deprecated)]
+            pub mod #module_ident;
+        }
+    });
+
+    let tokens = quote! {
+        #(#modules_tokens)*
+    };
+
+    writeln!(out, "{}", tokens).unwrap();
+}
+
+/// Crate root, `modules` are (module, public name) pairs.  A module with a
+/// different public name is declared under that name and aliased back to its
+/// module name, which is how the generated code refers to it.
+pub fn generate_bare<W: Write>(modules: &[(String, String)], out: &mut W) {
+    let modules_tokens = modules.iter().map(|(module, public)| {
+        let module_ident = ident(module);
+        let public_ident = ident(public);
+        let path = format!("{}/mod.rs", module);
+
+        if module == public {
+            quote! {
+                pub mod #module_ident;
+            }
+        } else {
+            quote! {
+                #[path = #path]
+                pub mod #public_ident;
+                use self::#public_ident as #module_ident;
+            }
+        }
+    });
+    let mav_string = emit_mav_string();
+    let mav_view = emit_mav_view();
+    let range_error = emit_range_error();
+    let length_error = emit_length_error();
+    let command_error = emit_command_error();
+    let buffer_error = emit_buffer_error();
+    let enum_name_error = emit_enum_name_error();
+    let dialect_spec = emit_dialect_spec();
+    let passthrough = emit_passthrough();
+
+    let tokens = quote! {
+        #(#modules_tokens)*
+        #mav_string
+        #mav_view
+        #range_error
+        #length_error
+        #command_error
+        #buffer_error
+        #enum_name_error
+        #dialect_spec
+        #passthrough
+    };
+
+    writeln!(out, "{}", tokens).unwrap();
+}
+
+/// `proto_sources()`, the proto files compiled into the crate so they can be
+/// used with other languages.
+pub fn generate_proto_sources<W: Write>(modules: &[String], out: &mut W) {
+    let files = std::iter::once("mav.proto".to_string())
+        .chain(modules.iter().map(|module| format!("{}.proto", module)))
+        .collect::<Vec<String>>();
+    let paths = files.iter().map(|file| format!("../protos/{}", file));
+    let tokens = quote! {
+        /// The proto files the `proto` structs are generated from as (file
+        /// name, contents), `mav.proto` holds the MAVLink options they use.
+        pub fn proto_sources() -> &'static [(&'static str, &'static str)] {
+            &[#((#files, include_str!(#paths))),*]
+        }
+    };
+
+    writeln!(out, "{}", tokens).unwrap();
+}
+
+/// `DESCRIPTOR_SET` of the proto module, written by prost_build next to the
+/// proto files, and `mav_options()` reading the `mav.proto` options back out
+/// of it.
+pub fn generate_descriptor_set<W: Write>(out: &mut W) {
+    let tokens = quote! {
+        /// The `mav.proto` option types.
+        pub mod mav;
+
+        /// Encoded `google.protobuf.FileDescriptorSet` of every dialect (and
+        /// the files they import), for protobuf reflection.
+        pub static DESCRIPTOR_SET: &[u8] = include_bytes!("../../protos/descriptor_set.bin");
+
+        /// The MAVLink options of one proto message.
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct MavMessageOptions {
+            /// Full proto name, e.g. "common.HEARTBEAT".
+            pub full_name: String,
+            /// `(mav.message).id`, the MAVLink message id.
+            pub id: Option<i32>,
+            /// `(mav.opts)` of the fields by field name, in proto order.
+            pub fields: Vec<(String, mav::MavFieldOptions)>,
+        }
+
+        /// Just the parts of `descriptor.proto` holding the mav.proto
+        /// options, the extensions (60066) decode like plain fields.
+        mod descriptor {
+            #[derive(Clone, PartialEq, prost::Message)]
+            pub struct FileDescriptorSet {
+                #[prost(message, repeated, tag = "1")]
+                pub file: Vec<FileDescriptorProto>,
+            }
+
+            #[derive(Clone, PartialEq, prost::Message)]
+            pub struct FileDescriptorProto {
+                #[prost(string, tag = "2")]
+                pub package: String,
+                #[prost(message, repeated, tag = "4")]
+                pub message_type: Vec<DescriptorProto>,
+            }
+
+            #[derive(Clone, PartialEq, prost::Message)]
+            pub struct DescriptorProto {
+                #[prost(string, tag = "1")]
+                pub name: String,
+                #[prost(message, repeated, tag = "2")]
+                pub field: Vec<FieldDescriptorProto>,
+                #[prost(message, optional, tag = "7")]
+                pub options: Option<MessageOptions>,
+            }
+
+            #[derive(Clone, PartialEq, prost::Message)]
+            pub struct FieldDescriptorProto {
+                #[prost(string, tag = "1")]
+                pub name: String,
+                #[prost(message, optional, tag = "8")]
+                pub options: Option<FieldOptions>,
+            }
+
+            #[derive(Clone, PartialEq, prost::Message)]
+            pub struct MessageOptions {
+                #[prost(message, optional, tag = "60066")]
+                pub message: Option<super::mav::MavMesOptions>,
+            }
+
+            #[derive(Clone, PartialEq, prost::Message)]
+            pub struct FieldOptions {
+                #[prost(message, optional, tag = "60066")]
+                pub opts: Option<super::mav::MavFieldOptions>,
+            }
+        }
+
+        /// The MAVLink options of every message in `DESCRIPTOR_SET`, for
+        /// recovering MAVLink ids and wire types from proto messages.
+        /// Decodes the descriptor set on every call.
+        pub fn mav_options() -> Vec<MavMessageOptions> {
+            use prost::Message;
+
+            let set = descriptor::FileDescriptorSet::decode(DESCRIPTOR_SET)
+                .expect("embedded descriptor set is valid");
+            set.file
+                .into_iter()
+                .flat_map(|file| {
+                    let package = file.package;
+                    file.message_type.into_iter().map(move |message| MavMessageOptions {
+                        full_name: format!("{}.{}", package, message.name),
+                        id: message.options.and_then(|options| options.message?.id),
+                        fields: message
+                            .field
+                            .into_iter()
+                            .filter_map(|field| Some((field.name, field.options?.opts?)))
+                            .collect(),
+                    })
+                })
+                .filter(|message| message.id.is_some() || !message.fields.is_empty())
+                .collect()
+        }
+    };
+
+    writeln!(out, "{}", tokens).unwrap();
+}
+
+/// Conversions between messages and the `MavFrame` of the gRPC service.
+#[cfg(feature = "grpc")]
+pub fn generate_grpc<W: Write>(out: &mut W) {
+    let tokens = quote! {
+        impl mav_stream::MavFrame {
+            /// Wrap `msg` of `dialect` (its module name, e.g. "common").
+            pub fn new<M: proto_mav_comm::Message>(
+                dialect: &str,
+                system_id: u8,
+                component_id: u8,
+                msg: &M,
+            ) -> Self {
+                Self {
+                    dialect: dialect.to_string(),
+                    system_id: system_id.into(),
+                    component_id: component_id.into(),
+                    message_id: msg.message_id(),
+                    payload: msg.proto_encode(),
+                }
+            }
+
+            /// Decode the payload with the `MavMessage` of a dialect.
+            pub fn message<M: proto_mav_comm::Message>(
+                &self,
+            ) -> Result<M, proto_mav_comm::error::ParserError> {
+                M::proto_parse(self.message_id, &self.payload)
+            }
+        }
+    };
+
+    writeln!(out, "{}", tokens).unwrap();
+}
+
+/// `ffi`, a C API over MAVLink frames of all dialects, see `include/proto_mav.h`.
+#[cfg(feature = "ffi")]
+pub fn generate_ffi<W: Write>(modules: &[String], out: &mut W) {
+    use crate::ffi::{FRAME_FIELDS, RETURN_CODES};
+
+    let modules = modules.iter().map(|module| ident(module));
+    let return_codes = RETURN_CODES.iter().map(|(name, value, description)| {
+        let name = ident(name);
+        let value = proc_macro2::Literal::i32_unsuffixed(*value);
+        let doc = (!description.is_empty()).then(|| {
+            let description = format!(" {}", description);
+            quote!(#[doc = #description])
+        });
+        quote! {
+            #doc
+            pub const #name: i32 = #value;
+        }
+    });
+    let frame_fields = FRAME_FIELDS
+        .iter()
+        .map(|(name, rust_type, len, description)| {
+            let name = ident(name);
+            let rust_type = ident(rust_type);
+            let rust_type = match len {
+                Some(len) => {
+                    let len = proc_macro2::Literal::usize_unsuffixed(*len);
+                    quote!([#rust_type; #len])
+                }
+                None => quote!(#rust_type),
+            };
+            let doc = (!description.is_empty()).then(|| {
+                let description = format!(" {}", description);
+                quote!(#[doc = #description])
+            });
+            quote! {
+                #doc
+                pub #name: #rust_type,
+            }
+        });
+    let tokens = quote! {
+        pub mod ffi {
+            //! Parse and serialize MAVLink frames from C.  Message ids are looked
+            //! up in every dialect, in module order, for their crc_extra.
+
+            use std::os::raw::c_char;
+
+            use crate::DialectSpec;
+
+            #(#return_codes)*
+
+            const MAGIC_V1: u8 = 0xfe;
+            const MAGIC_V2: u8 = 0xfd;
+            const SIGNATURE_LEN: usize = 13;
+
+            /// One MAVLink frame, the payload as on the wire.
+            #[repr(C)]
+            #[derive(Clone, Copy)]
+            pub struct ProtoMavFrame {
+                #(#frame_fields)*
+            }
+
+            static DIALECTS: &[&(dyn DialectSpec + Sync)] = &[#(&crate::mavlink::#modules::Dialect),*];
+
+            fn message_info(id: u32) -> Option<crate::MessageInfo> {
+                DIALECTS.iter().find_map(|dialect| dialect.message_info(id))
+            }
+
+
+            /// Parse the frame at the start of `buf` into `out_frame`.  Returns
+            /// the length of the frame (signature included), or a negative
+            /// `PROTO_MAV_ERR_*`.
+            ///
+            /// # Safety
+            ///
+            /// `buf` has to be valid for `len` bytes and `out_frame` point to a
+            /// writable `ProtoMavFrame`.
+            #[no_mangle]
+            pub unsafe extern "C" fn proto_mav_parse(
+                buf: *const u8,
+                len: usize,
+                out_frame: *mut ProtoMavFrame,
+            ) -> i32 {
+                if buf.is_null() || out_frame.is_null() {
+                    return PROTO_MAV_ERR_NULL;
+                }
+                let buf = std::slice::from_raw_parts(buf, len);
+                let (version, header_len) = match buf.first() {
+                    None => return PROTO_MAV_ERR_INCOMPLETE,
+                    Some(&MAGIC_V1) => (1, 6),
+                    Some(&MAGIC_V2) => (2, 10),
+                    Some(_) => return PROTO_MAV_ERR_MAGIC,
+                };
+                if buf.len() < header_len {
+                    return PROTO_MAV_ERR_INCOMPLETE;
+                }
+                let payload_len = usize::from(buf[1]);
+                let (sequence, system_id, component_id, message_id, signed) = if version == 1 {
+                    (buf[2], buf[3], buf[4], u32::from(buf[5]), false)
+                } else {
+                    let id = u32::from_le_bytes([buf[7], buf[8], buf[9], 0]);
+                    (buf[4], buf[5], buf[6], id, buf[2] & 1 != 0)
+                };
+                let crc_end = header_len + payload_len;
+                let frame_len = crc_end + 2 + if signed { SIGNATURE_LEN } else { 0 };
+                if buf.len() < frame_len {
+                    return PROTO_MAV_ERR_INCOMPLETE;
+                }
+                let info = match message_info(message_id) {
+                    Some(info) => info,
+                    None => return PROTO_MAV_ERR_UNKNOWN_MESSAGE,
+                };
+                let checksum = u16::from_le_bytes([buf[crc_end], buf[crc_end + 1]]);
+                if crate::frame_crc(&buf[1..crc_end], info.extra_crc) != checksum {
+                    return PROTO_MAV_ERR_CRC;
+                }
+                let mut payload = [0; 255];
+                payload[..payload_len].copy_from_slice(&buf[header_len..crc_end]);
+                *out_frame = ProtoMavFrame {
+                    version,
+                    sequence,
+                    system_id,
+                    component_id,
+                    message_id,
+                    payload_len: payload_len as u8,
+                    payload,
+                };
+                frame_len as i32
+            }
+
+            /// Write `frame` to `buf` (unsigned, MAVLink 2 payloads without
+            /// their trailing zeros).  Returns the number of bytes written, or a
+            /// negative `PROTO_MAV_ERR_*`.
+            ///
+            /// # Safety
+            ///
+            /// `frame` has to point to a `ProtoMavFrame` and `buf` be writable
+            /// for `cap` bytes.
+            #[no_mangle]
+            pub unsafe extern "C" fn proto_mav_serialize(
+                frame: *const ProtoMavFrame,
+                buf: *mut u8,
+                cap: usize,
+            ) -> i32 {
+                if frame.is_null() || buf.is_null() {
+                    return PROTO_MAV_ERR_NULL;
+                }
+                let frame = &*frame;
+                let buf = std::slice::from_raw_parts_mut(buf, cap);
+                let info = match message_info(frame.message_id) {
+                    Some(info) => info,
+                    None => return PROTO_MAV_ERR_UNKNOWN_MESSAGE,
+                };
+                let mut payload = &frame.payload[..usize::from(frame.payload_len)];
+                let id = frame.message_id.to_le_bytes();
+                let mut header = [0; 10];
+                let header = match frame.version {
+                    1 if frame.message_id <= 0xff => {
+                        header[..6].copy_from_slice(&[
+                            MAGIC_V1,
+                            frame.payload_len,
+                            frame.sequence,
+                            frame.system_id,
+                            frame.component_id,
+                            id[0],
+                        ]);
+                        &header[..6]
+                    }
+                    2 if frame.message_id <= 0xff_ffff => {
+                        // at least one byte stays
+                        while payload.len() > 1 && payload[payload.len() - 1] == 0 {
+                            payload = &payload[..payload.len() - 1];
+                        }
+                        header = [
+                            MAGIC_V2,
+                            payload.len() as u8,
+                            0,
+                            0,
+                            frame.sequence,
+                            frame.system_id,
+                            frame.component_id,
+                            id[0],
+                            id[1],
+                            id[2],
+                        ];
+                        &header[..]
+                    }
+                    _ => return PROTO_MAV_ERR_INVALID,
+                };
+                let crc_end = header.len() + payload.len();
+                if cap < crc_end + 2 {
+                    return PROTO_MAV_ERR_BUFFER_TOO_SMALL;
+                }
+                buf[..header.len()].copy_from_slice(header);
+                buf[header.len()..crc_end].copy_from_slice(payload);
+                let checksum = crate::frame_crc(&buf[1..crc_end], info.extra_crc);
+                buf[crc_end..crc_end + 2].copy_from_slice(&checksum.to_le_bytes());
+                (crc_end + 2) as i32
+            }
+
+            /// Convert `frame` to MAVLink `version` in place.  MAVLink 1 has
+            /// no extension fields, they are cut off and the trailing zeros
+            /// MAVLink 2 left out are put back.  Returns 0, `PROTO_MAV_LOSSY`
+            /// if an extension field was not zero, or a negative
+            /// `PROTO_MAV_ERR_*` (a message id above 255 can not be sent as
+            /// MAVLink 1).
+            ///
+            /// # Safety
+            ///
+            /// `frame` has to point to a writable `ProtoMavFrame`.
+            #[no_mangle]
+            pub unsafe extern "C" fn proto_mav_convert(
+                frame: *mut ProtoMavFrame,
+                version: u8,
+            ) -> i32 {
+                if frame.is_null() {
+                    return PROTO_MAV_ERR_NULL;
+                }
+                let frame = &mut *frame;
+                let info = match message_info(frame.message_id) {
+                    Some(info) => info,
+                    None => return PROTO_MAV_ERR_UNKNOWN_MESSAGE,
+                };
+                let payload_len = usize::from(frame.payload_len);
+                match (frame.version, version) {
+                    (1, 1) | (2, 2) => 0,
+                    (1, 2) => {
+                        frame.version = 2;
+                        0
+                    }
+                    (2, 1) if frame.message_id <= 0xff => {
+                        let len = info.min_encoded_len;
+                        let lossy = payload_len > len
+                            && frame.payload[len..payload_len].iter().any(|&b| b != 0);
+                        for byte in &mut frame.payload[payload_len.min(len)..] {
+                            *byte = 0;
+                        }
+                        frame.version = 1;
+                        frame.payload_len = len as u8;
+                        if lossy {
+                            PROTO_MAV_LOSSY
+                        } else {
+                            0
+                        }
+                    }
+                    _ => PROTO_MAV_ERR_INVALID,
+                }
+            }
+
+            /// Id of the message named `name`, as `Message::message_name()`
+            /// ("SysStatus") or as in the XML ("SYS_STATUS").  Negative
+            /// `PROTO_MAV_ERR_*` if there is no such message.
+            ///
+            /// # Safety
+            ///
+            /// `name` has to be a NUL terminated string.
+            #[no_mangle]
+            pub unsafe extern "C" fn proto_mav_message_id(name: *const c_char) -> i32 {
+                if name.is_null() {
+                    return PROTO_MAV_ERR_NULL;
+                }
+                let name = std::ffi::CStr::from_ptr(name).to_bytes();
+                let name = name.iter().filter(|&&c| c != b'_');
+                DIALECTS
+                    .iter()
+                    .flat_map(|dialect| dialect.messages())
+                    .find(|info| {
+                        info.name.len() == name.clone().count()
+                            && info
+                                .name
+                                .bytes()
+                                .zip(name.clone())
+                                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+                    })
+                    .map_or(PROTO_MAV_ERR_UNKNOWN_MESSAGE, |info| info.id as i32)
+            }
+
+            /// Write the name of message `id` ("SysStatus") NUL terminated to
+            /// `buf`.  Returns its length without the NUL, or a negative
+            /// `PROTO_MAV_ERR_*`.
+            ///
+            /// # Safety
+            ///
+            /// `buf` has to be writable for `cap` bytes.
+            #[no_mangle]
+            pub unsafe extern "C" fn proto_mav_message_name(
+                id: u32,
+                buf: *mut c_char,
+                cap: usize,
+            ) -> i32 {
+                if buf.is_null() {
+                    return PROTO_MAV_ERR_NULL;
+                }
+                let name = match message_info(id) {
+                    Some(info) => info.name,
+                    None => return PROTO_MAV_ERR_UNKNOWN_MESSAGE,
+                };
+                if cap <= name.len() {
+                    return PROTO_MAV_ERR_BUFFER_TOO_SMALL;
+                }
+                let buf = std::slice::from_raw_parts_mut(buf as *mut u8, cap);
+                buf[..name.len()].copy_from_slice(name.as_bytes());
+                buf[name.len()] = 0;
+                name.len() as i32
+            }
+        }
+    };
+
+    writeln!(out, "{}", tokens).unwrap();
+}
+
+/// `SCHEMAS`, the JSON schema of every dialect keyed by module name.
+#[cfg(feature = "schema")]
+pub fn generate_schemas<W: Write>(modules: &[String], out: &mut W) {
+    let names = modules.iter().map(|module| module.as_str());
+    let modules = modules.iter().map(|module| ident(module));
+    let tokens = quote! {
+        /// The JSON schema of the messages of every dialect, keyed by module
+        /// name.
+        pub static SCHEMAS: &[(&str, &str)] = &[#((#names, #modules::SCHEMA)),*];
+    };
+
+    writeln!(out, "{}", tokens).unwrap();
+}
+
+/// Helpers shared by the generated char array readers and writers.
+///
+/// MAVLink char arrays are raw bytes on the wire but are exposed as `String`
+/// in the proto structs.  Bytes that are not valid UTF-8 (and code points that
+/// would collide with the escape range) are mapped one to one onto the last
+/// 256 code points of the supplementary private use area so that the original
+/// bytes can always be recovered.
+fn emit_mav_string() -> TokenStream {
+    quote! {
+        pub mod mav_string {
+            const ESCAPE_BASE: u32 = 0x0010_ff00;
+
+            fn is_escape(c: char) -> bool {
+                (c as u32) >= ESCAPE_BASE
+            }
+
+            fn push_escaped(out: &mut String, bytes: &[u8]) {
+                for b in bytes {
+                    out.push(std::char::from_u32(ESCAPE_BASE + *b as u32).unwrap());
+                }
+            }
+
+            fn push_valid(out: &mut String, s: &str) {
+                for c in s.chars() {
+                    if is_escape(c) {
+                        let mut tmp = [0; 4];
+                        push_escaped(out, c.encode_utf8(&mut tmp).as_bytes());
+                    } else {
+                        out.push(c);
+                    }
+                }
+            }
+
+            /// Decode the raw bytes of a char array without losing any of them.
+            pub fn from_bytes(bytes: &[u8]) -> String {
+                let mut out = String::with_capacity(bytes.len());
+                let mut rest = bytes;
+                while !rest.is_empty() {
+                    match std::str::from_utf8(rest) {
+                        Ok(s) => {
+                            push_valid(&mut out, s);
+                            break;
+                        }
+                        Err(e) => {
+                            let (valid, invalid) = rest.split_at(e.valid_up_to());
+                            push_valid(&mut out, std::str::from_utf8(valid).unwrap());
+                            let bad_len = e.error_len().unwrap_or_else(|| invalid.len());
+                            push_escaped(&mut out, &invalid[..bad_len]);
+                            rest = &invalid[bad_len..];
+                        }
+                    }
+                }
+                out
+            }
+
+            /// Recover the raw char array bytes from a string produced by `from_bytes`.
+            pub fn to_bytes(s: &str) -> Vec<u8> {
+                let mut out = Vec::with_capacity(s.len());
+                for c in s.chars() {
+                    if is_escape(c) {
+                        out.push((c as u32 - ESCAPE_BASE) as u8);
+                    } else {
+                        let mut tmp = [0; 4];
+                        out.extend_from_slice(c.encode_utf8(&mut tmp).as_bytes());
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+/// Helpers used by the generated `*View` accessors.
+fn emit_mav_view() -> TokenStream {
+    quote! {
+        pub mod mav_view {
+            /// Copy `N` bytes starting at `offset`, bytes past the end of a
+            /// (truncated) payload read as zero.
+            pub fn bytes<const N: usize>(payload: &[u8], offset: usize) -> [u8; N] {
+                let mut out = [0; N];
+                if offset < payload.len() {
+                    let end = std::cmp::min(payload.len(), offset + N);
+                    out[..end - offset].copy_from_slice(&payload[offset..end]);
+                }
+                out
+            }
+
+            /// The available part of `len` bytes starting at `offset`.
+            pub fn slice(payload: &[u8], offset: usize, len: usize) -> &[u8] {
+                let start = std::cmp::min(payload.len(), offset);
+                let end = std::cmp::min(payload.len(), offset + len);
+                &payload[start..end]
+            }
+        }
+    }
+}
+
+/// Error returned by the generated `set_*_checked` setters.
+fn emit_range_error() -> TokenStream {
+    quote! {
+        /// A value does not fit the MAVLink type of the field it was set on.
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct RangeError {
+            pub field: &'static str,
+            pub value: i64,
+            pub min: i64,
+            pub max: i64,
+        }
+
+        impl std::fmt::Display for RangeError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "value {} for {} is outside of {}..={}",
+                    self.value, self.field, self.min, self.max
+                )
+            }
+        }
+
+        impl std::error::Error for RangeError {}
+    }
+}
+
+/// Error returned by the generated `validate()` of messages with strings.
+fn emit_length_error() -> TokenStream {
+    quote! {
+        /// A string is longer than the char array of its field.
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct LengthError {
+            pub field: &'static str,
+            pub len: usize,
+            pub max_len: usize,
+        }
+
+        impl std::fmt::Display for LengthError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "{} is {} bytes long, at most {} fit",
+                    self.field, self.len, self.max_len
+                )
+            }
+        }
+
+        impl std::error::Error for LengthError {}
+    }
+}
+
+/// Error returned converting a COMMAND_LONG or COMMAND_INT to the struct of
+/// another command.
+fn emit_command_error() -> TokenStream {
+    quote! {
+        /// The message carries another MAV_CMD than the command struct.
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct CommandError {
+            pub expected: u32,
+            pub found: u32,
+        }
+
+        impl std::fmt::Display for CommandError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "expected command {}, found {}", self.expected, self.found)
+            }
+        }
+
+        impl std::error::Error for CommandError {}
+    }
+}
+
+/// Error returned by the generated `mavlink_ser_into()` for a buffer that
+/// can not hold the payload.
+fn emit_buffer_error() -> TokenStream {
+    quote! {
+        /// The buffer is shorter than the MAVLink payload.
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct BufferError {
+            pub len: usize,
+            pub needed: usize,
+        }
+
+        impl std::fmt::Display for BufferError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(
+                    f,
+                    "buffer of {} bytes is too short, the payload needs {}",
+                    self.len, self.needed
+                )
+            }
+        }
+
+        impl std::error::Error for BufferError {}
+    }
+}
+
+/// Error returned by the generated `FromStr` of enums.
+fn emit_enum_name_error() -> TokenStream {
+    quote! {
+        /// The string is not the MAVLink name of an entry of the enum.
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct EnumNameError {
+            pub enum_type: &'static str,
+            pub name: String,
+        }
+
+        impl std::fmt::Display for EnumNameError {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{} is not an entry of {}", self.name, self.enum_type)
+            }
+        }
+
+        impl std::error::Error for EnumNameError {}
+    }
+}
+
+/// `Passthrough<M>`, a dialect's messages plus the raw payloads of the ids
+/// it does not know, for routers and loggers that forward everything.
+fn emit_passthrough() -> TokenStream {
+    quote! {
+        /// How the payload of a `Passthrough::Unknown` is encoded.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub enum PayloadEncoding {
+            /// MAVLink wire format, from `parse()`.
+            Mavlink,
+            /// Protobuf, from `proto_parse()`.
+            Proto,
+            /// The whole MAVLink frame as received, header, checksum and
+            /// signature included, from `Passthrough::parse_frame()`.
+            Frame,
+        }
+
+        /// X.25 checksum of a MAVLink frame.
+        pub(crate) fn frame_crc(bytes: &[u8], extra_crc: u8) -> u16 {
+            bytes
+                .iter()
+                .chain(std::iter::once(&extra_crc))
+                .fold(0xffff, |crc: u16, byte| {
+                    let mut tmp = byte ^ (crc as u8);
+                    tmp ^= tmp << 4;
+                    let tmp = u16::from(tmp);
+                    (crc >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4)
+                })
+        }
+
+        /// Error of `Passthrough::parse_frame()`.
+        #[derive(Debug)]
+        pub enum FrameError {
+            /// The buffer ends before the frame does.
+            Incomplete,
+            /// The buffer does not start with a MAVLink 1 or 2 magic byte.
+            Magic,
+            /// Wrong checksum in the frame of a known message.
+            Crc,
+            /// The payload of a known message does not parse.
+            Parser(proto_mav_comm::error::ParserError),
+        }
+
+        /// A message of the dialect `M`, or a message with an id `M` does not
+        /// know.  Unknown messages are kept as they were received and
+        /// serialize back to the same bytes in the same encoding, so a proxy
+        /// can forward them.  The serializer of the other encoding returns an
+        /// empty payload, an unknown message can not be converted.
+        ///
+        /// Readers checking the checksum with `extra_crc()` drop the frames
+        /// of unknown ids (it is 0 for them), and a frame written with it
+        /// has a checksum receivers reject.  `parse_frame()` keeps the whole
+        /// frame of an unknown id instead, `frame()` re-emits it byte for
+        /// byte.
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum Passthrough<M> {
+            Known(M),
+            Unknown {
+                id: u32,
+                /// The payload, the whole frame for `PayloadEncoding::Frame`.
+                payload: Vec<u8>,
+                encoding: PayloadEncoding,
+            },
+        }
+
+        impl<M> Passthrough<M> {
+            /// The parsed message, `None` for an unknown id.
+            pub fn known(&self) -> Option<&M> {
+                match self {
+                    Passthrough::Known(msg) => Some(msg),
+                    Passthrough::Unknown { .. } => None,
+                }
+            }
+
+            /// The frame of an unknown message as received by
+            /// `parse_frame()`, to forward it unchanged.
+            pub fn frame(&self) -> Option<&[u8]> {
+                match self {
+                    Passthrough::Unknown {
+                        payload,
+                        encoding: PayloadEncoding::Frame,
+                        ..
+                    } => Some(payload),
+                    _ => None,
+                }
+            }
+        }
+
+        impl<M> From<M> for Passthrough<M> {
+            fn from(msg: M) -> Self {
+                Passthrough::Known(msg)
+            }
+        }
+
+        impl<M: proto_mav_comm::Message> Passthrough<M> {
+            /// Parse the MAVLink 1 or 2 frame at the start of `buf`, returns
+            /// the message and the length of the frame (signature included).
+            /// The checksum of a known message is checked, the frame of an
+            /// unknown id can not be checked and is kept whole.
+            pub fn parse_frame(buf: &[u8]) -> Result<(Self, usize), FrameError> {
+                let (version, header_len) = match buf.first() {
+                    None => return Err(FrameError::Incomplete),
+                    Some(0xfe) => (proto_mav_comm::MavlinkVersion::V1, 6),
+                    Some(0xfd) => (proto_mav_comm::MavlinkVersion::V2, 10),
+                    Some(_) => return Err(FrameError::Magic),
+                };
+                if buf.len() < header_len {
+                    return Err(FrameError::Incomplete);
+                }
+                let (id, signed) = match version {
+                    proto_mav_comm::MavlinkVersion::V1 => (u32::from(buf[5]), false),
+                    proto_mav_comm::MavlinkVersion::V2 => {
+                        (u32::from_le_bytes([buf[7], buf[8], buf[9], 0]), buf[2] & 1 != 0)
+                    }
+                };
+                let crc_end = header_len + usize::from(buf[1]);
+                let frame_len = crc_end + 2 + if signed { 13 } else { 0 };
+                if buf.len() < frame_len {
+                    return Err(FrameError::Incomplete);
+                }
+                if M::default_message_from_id(id).is_err() {
+                    let unknown = Passthrough::Unknown {
+                        id,
+                        payload: buf[..frame_len].to_vec(),
+                        encoding: PayloadEncoding::Frame,
+                    };
+                    return Ok((unknown, frame_len));
+                }
+                let checksum = u16::from_le_bytes([buf[crc_end], buf[crc_end + 1]]);
+                if frame_crc(&buf[1..crc_end], M::extra_crc(id)) != checksum {
+                    return Err(FrameError::Crc);
+                }
+                M::parse(version, id, &buf[header_len..crc_end])
+                    .map(|msg| (Passthrough::Known(msg), frame_len))
+                    .map_err(FrameError::Parser)
+            }
+
+            fn or_unknown(
+                result: Result<M, proto_mav_comm::error::ParserError>,
+                payload: &[u8],
+                encoding: PayloadEncoding,
+            ) -> Result<Self, proto_mav_comm::error::ParserError> {
+                match result {
+                    Ok(msg) => Ok(Passthrough::Known(msg)),
+                    Err(proto_mav_comm::error::ParserError::UnknownMessage { id }) => {
+                        Ok(Passthrough::Unknown {
+                            id,
+                            payload: payload.to_vec(),
+                            encoding,
+                        })
+                    }
+                    Err(error) => Err(error),
+                }
+            }
+
+            /// The payload of an unknown message if it has `encoding`, a
+            /// frame has a MAVLink payload.
+            fn unknown_payload(&self, wanted: PayloadEncoding) -> Vec<u8> {
+                match self {
+                    Passthrough::Unknown {
+                        payload, encoding, ..
+                    } if *encoding == wanted => payload.clone(),
+                    Passthrough::Unknown {
+                        payload,
+                        encoding: PayloadEncoding::Frame,
+                        ..
+                    } if wanted == PayloadEncoding::Mavlink => {
+                        let header_len = if payload[0] == 0xfd { 10 } else { 6 };
+                        payload[header_len..header_len + usize::from(payload[1])].to_vec()
+                    }
+                    _ => Vec::new(),
+                }
+            }
+        }
+
+        /// `extra_crc()` is 0 for an unknown id, see `parse_frame()` for
+        /// their frames.
+        impl<M: proto_mav_comm::Message> proto_mav_comm::Message for Passthrough<M> {
+            fn parse(
+                version: proto_mav_comm::MavlinkVersion,
+                id: u32,
+                payload: &[u8],
+            ) -> Result<Self, proto_mav_comm::error::ParserError> {
+                let result = M::parse(version, id, payload);
+                Passthrough::or_unknown(result, payload, PayloadEncoding::Mavlink)
+            }
+
+            fn proto_parse(
+                id: u32,
+                payload: &[u8],
+            ) -> Result<Self, proto_mav_comm::error::ParserError> {
+                let result = M::proto_parse(id, payload);
+                Passthrough::or_unknown(result, payload, PayloadEncoding::Proto)
+            }
+
+            fn message_id(&self) -> u32 {
+                match self {
+                    Passthrough::Known(msg) => msg.message_id(),
+                    Passthrough::Unknown { id, .. } => *id,
+                }
+            }
+
+            fn message_name(&self) -> &'static str {
+                match self {
+                    Passthrough::Known(msg) => msg.message_name(),
+                    Passthrough::Unknown { .. } => "Unknown",
+                }
+            }
+
+            fn message_id_from_name(name: &str) -> Result<u32, &'static str> {
+                M::message_id_from_name(name)
+            }
+
+            fn default_message_from_id(id: u32) -> Result<Self, &'static str> {
+                M::default_message_from_id(id).map(Passthrough::Known)
+            }
+
+            fn mavlink_ser(&self) -> Vec<u8> {
+                match self {
+                    Passthrough::Known(msg) => msg.mavlink_ser(),
+                    Passthrough::Unknown { .. } => self.unknown_payload(PayloadEncoding::Mavlink),
+                }
+            }
+
+            fn proto_encode(&self) -> Vec<u8> {
+                match self {
+                    Passthrough::Known(msg) => msg.proto_encode(),
+                    Passthrough::Unknown { .. } => self.unknown_payload(PayloadEncoding::Proto),
+                }
+            }
+
+            fn extra_crc(id: u32) -> u8 {
+                M::extra_crc(id)
+            }
+        }
+    }
+}
+
+/// Object safe view of a dialect, implemented by every generated dialect's
+/// `Dialect` unit struct.
+fn emit_dialect_spec() -> TokenStream {
+    quote! {
+        /// Static information about one message of a dialect.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct MessageInfo {
+            pub id: u32,
+            pub name: &'static str,
+            pub extra_crc: u8,
+            pub encoded_len: usize,
+            /// Payload length without extension fields (the MAVLink 1 length).
+            pub min_encoded_len: usize,
+            /// Whether the message has extension fields in the XML, also
+            /// when they are not generated (without the emit-extensions
+            /// feature).  `min_encoded_len < encoded_len` when they are.
+            pub has_extensions: bool,
+            /// The fields in wire order.
+            pub fields: &'static [FieldInfo],
+        }
+
+        /// Static information about one field of a message.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct FieldInfo {
+            /// Name of the field in the structs.
+            pub name: &'static str,
+            /// MAVLink type as in the XML, e.g. "uint16_t" or "char[16]".
+            pub mav_type: &'static str,
+            /// MAVLink name of the enum the values come from.
+            pub enum_type: Option<&'static str>,
+            pub units: Option<&'static str>,
+            pub is_extension: bool,
+        }
+
+        /// Static information about a MAV_CMD entry, for building command
+        /// editors.
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct CommandInfo {
+            /// MAV_CMD value.
+            pub id: u32,
+            /// Name of the command struct, e.g. "NavTakeoff".
+            pub name: &'static str,
+            /// The params given in the XML, in index order.
+            pub params: &'static [CommandParamInfo],
+        }
+
+        /// Static information about one param of a command.
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        pub struct CommandParamInfo {
+            /// 1 based, param1 to param7 of COMMAND_LONG.
+            pub index: u8,
+            pub label: Option<&'static str>,
+            pub description: &'static str,
+            pub units: Option<&'static str>,
+            /// MAVLink name of the enum the values come from.
+            pub enum_type: Option<&'static str>,
+            pub min_value: Option<f32>,
+            pub max_value: Option<f32>,
+            pub increment: Option<f32>,
+            /// Reserved params are sent as `default`.
+            pub reserved: bool,
+            pub default: Option<f32>,
+        }
+
+        /// A decoded message of any dialect.
+        pub trait DynMessage: std::fmt::Debug {
+            fn message_id(&self) -> u32;
+            fn message_name(&self) -> &'static str;
+            fn mavlink_ser(&self) -> Vec<u8>;
+            fn proto_encode(&self) -> Vec<u8>;
+        }
+
+        impl<M: proto_mav_comm::Message + std::fmt::Debug> DynMessage for M {
+            fn message_id(&self) -> u32 {
+                proto_mav_comm::Message::message_id(self)
+            }
+
+            fn message_name(&self) -> &'static str {
+                proto_mav_comm::Message::message_name(self)
+            }
+
+            fn mavlink_ser(&self) -> Vec<u8> {
+                proto_mav_comm::Message::mavlink_ser(self)
+            }
+
+            fn proto_encode(&self) -> Vec<u8> {
+                proto_mav_comm::Message::proto_encode(self)
+            }
+        }
+
+        /// `<version>` and `<dialect>` of a dialect's definitions.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+        pub struct DialectInfo {
+            /// Module name of the dialect, e.g. "common".
+            pub name: &'static str,
+            pub mavlink_version: &'static str,
+            pub dialect_number: u8,
+        }
+
+        /// A dialect as a runtime object, so tools can be written once for any dialect.
+        pub trait DialectSpec {
+            /// Module name of the dialect, e.g. "common".
+            fn name(&self) -> &'static str;
+
+            /// Version and dialect number of the definitions.
+            fn info(&self) -> DialectInfo;
+
+            fn message_info(&self, id: u32) -> Option<MessageInfo>;
+
+            /// Every message of the dialect, including those of its includes.
+            fn messages(&self) -> &'static [MessageInfo];
+
+            /// Every MAV_CMD entry usable with the dialect, by value.
+            fn commands(&self) -> &'static [CommandInfo];
+
+            fn command_info(&self, id: u32) -> Option<&'static CommandInfo> {
+                self.commands().iter().find(|command| command.id == id)
+            }
+
+            /// crc_extra of a message, `None` if the id is unknown.
+            fn extra_crc(&self, id: u32) -> Option<u8> {
+                self.message_info(id).map(|info| info.extra_crc)
+            }
+
+            fn parse(
+                &self,
+                version: proto_mav_comm::MavlinkVersion,
+                id: u32,
+                payload: &[u8],
+            ) -> Result<Box<dyn DynMessage>, proto_mav_comm::error::ParserError>;
+        }
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use toml::Value;
+
+use crate::util::to_module_name;
+
+/// Environment variable that can point at an alternate build config file.
+pub const CONFIG_ENV: &str = "PROTO_MAV_CONFIG";
+/// Config file looked for in the crate root when `PROTO_MAV_CONFIG` is not set.
+pub const CONFIG_FILE: &str = "proto-mav.toml";
+
+/// Build time options, read from `proto-mav.toml` in the crate root (or the
+/// file named by `PROTO_MAV_CONFIG`).  Everything is optional, a missing file
+/// gives the default configuration.  Options are added over time, from code
+/// start with `BuildConfig::default()` (or `load`) and set fields on it.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct BuildConfig {
+    /// (path, attribute) pairs passed to prost_build `type_attribute`.
+    pub type_attributes: Vec<(String, String)>,
+    /// (path, attribute) pairs passed to prost_build `field_attribute`.
+    pub field_attributes: Vec<(String, String)>,
+    /// Called with the prost_build config after the attributes are added,
+    /// for anything the config file does not cover.  Only set from code, a
+    /// plain `fn` can not capture configuration.  It must not change field
+    /// types, the MAVLink code depends on them.
+    pub prost_hook: Option<fn(&mut prost_build::Config)>,
+    /// Annotate fields narrower than the proto type with their MAVLink
+    /// value range (`min`/`max` in the `mav.opts` field option).
+    pub proto_int_ranges: bool,
+    /// Keep the MAVLink descriptions as doc comments on the prost types.
+    pub proto_comments: bool,
+    /// Store uint8_t arrays as proto `bytes` instead of `repeated uint32`.
+    pub proto_u8_bytes: bool,
+    /// Prefix of the proto packages, "mavlink" gives `package mavlink.common;`.
+    pub proto_package_prefix: Option<String>,
+    /// Public name of the module holding the MAVLink (de)serialization code.
+    pub mavlink_module: String,
+    /// Public name of the module holding the prost generated structs.
+    pub proto_module: String,
+    /// Module name overrides keyed by definition file name ("ASLUAV.xml").
+    pub module_names: HashMap<String, String>,
+    /// Named groups of bitmask entries keyed by enum name, e.g.
+    /// MAV_SYS_STATUS_SENSOR => [("required_for_arming", [entry names])].
+    pub bitmask_groups: HashMap<String, Vec<(String, Vec<String>)>>,
+    /// Only generate these messages (MAVLink names) and the enums they use.
+    pub messages: Option<Vec<String>>,
+    /// Write a cargo-fuzz crate to `fuzz/` with a target per dialect.
+    pub fuzz_targets: bool,
+    /// Name of the generated crate, used by the fuzz crate.
+    pub fuzz_crate_name: String,
+    /// Module names of dialects whose messages may be past the MAVLink frame
+    /// limit without a build warning, e.g. dialects only used by tests.
+    pub oversized_dialects: Vec<String>,
+}
+
+impl Default for BuildConfig {
+    fn default() -> BuildConfig {
+        BuildConfig {
+            type_attributes: vec![],
+            field_attributes: vec![],
+            prost_hook: None,
+            proto_int_ranges: false,
+            proto_comments: true,
+            proto_u8_bytes: false,
+            proto_package_prefix: None,
+            mavlink_module: "mavlink".to_string(),
+            proto_module: "proto".to_string(),
+            module_names: HashMap::new(),
+            bitmask_groups: HashMap::new(),
+            messages: None,
+            fuzz_targets: false,
+            fuzz_crate_name: "proto_mav_gen".to_string(),
+            oversized_dialects: vec![],
+        }
+    }
+}
+
+impl BuildConfig {
+    /// Location of the config file, which does not have to exist.
+    pub fn path(src_dir: &Path) -> PathBuf {
+        match env::var_os(CONFIG_ENV) {
+            Some(path) => PathBuf::from(path),
+            None => src_dir.join(CONFIG_FILE),
+        }
+    }
+
+    pub fn load(src_dir: &Path) -> BuildConfig {
+        println!("cargo:rerun-if-env-changed={}", CONFIG_ENV);
+        let path = BuildConfig::path(src_dir);
+        if !path.exists() {
+            return BuildConfig::default();
+        }
+        println!("cargo:rerun-if-changed={}", path.to_string_lossy());
+        let text = fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+        BuildConfig::parse(&text)
+            .unwrap_or_else(|e| panic!("Invalid build config {}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<BuildConfig, String> {
+        let root = text.parse::<Value>().map_err(|e| e.to_string())?;
+        let mut config = BuildConfig::default();
+        if let Some(proto) = root.get("proto") {
+            config.proto_int_ranges = get_bool(proto, "int_ranges")?.unwrap_or(false);
+            config.proto_comments = get_bool(proto, "comments")?.unwrap_or(true);
+            config.proto_u8_bytes = get_bool(proto, "u8_arrays_as_bytes")?.unwrap_or(false);
+            if let Some(prefix) = proto.get("package_prefix") {
+                let prefix = prefix
+                    .as_str()
+                    .ok_or_else(|| "proto.package_prefix must be a string".to_string())?;
+                for part in prefix.split('.') {
+                    as_ident(&Value::String(part.to_string()), "proto.package_prefix")?;
+                }
+                config.proto_package_prefix = Some(prefix.to_string());
+            }
+        }
+        if let Some(prost) = root.get("prost") {
+            let prefix = config.proto_package_prefix.as_deref();
+            config.type_attributes = prost_attributes(prost, "type_attribute", prefix)?;
+            config.field_attributes = prost_attributes(prost, "field_attribute", prefix)?;
+        }
+        if let Some(layout) = root.get("layout") {
+            if let Some(name) = get_ident(layout, "mavlink_module")? {
+                config.mavlink_module = name;
+            }
+            if let Some(name) = get_ident(layout, "proto_module")? {
+                config.proto_module = name;
+            }
+            if config.mavlink_module == config.proto_module {
+                return Err("layout.mavlink_module and layout.proto_module must differ".into());
+            }
+            match layout.get("module_names") {
+                Some(Value::Table(names)) => {
+                    for (file, name) in names {
+                        let name = as_ident(name, file)?;
+                        config.module_names.insert(file.clone(), name);
+                    }
+                }
+                Some(_) => return Err("layout.module_names must be a table".into()),
+                None => (),
+            }
+        }
+        if let Some(fuzz) = root.get("fuzz") {
+            config.fuzz_targets = get_bool(fuzz, "targets")?.unwrap_or(false);
+            match fuzz.get("crate_name") {
+                Some(Value::String(name)) => config.fuzz_crate_name = name.clone(),
+                Some(_) => return Err("fuzz.crate_name must be a string".into()),
+                None => (),
+            }
+        }
+        if let Some(messages) = root.get("messages") {
+            config.messages = Some(get_strings(messages, "allow")?.unwrap_or_default());
+        }
+        match root.get("bitmask_groups") {
+            Some(Value::Table(enums)) => {
+                for (enum_name, groups) in enums {
+                    config
+                        .bitmask_groups
+                        .insert(enum_name.clone(), bitmask_groups(enum_name, groups)?);
+                }
+            }
+            Some(_) => return Err("bitmask_groups must be a table".into()),
+            None => (),
+        }
+        Ok(config)
+    }
+
+    /// Rust module name of a definition file, also its proto package (after
+    /// the package prefix).
+    pub fn module_name<P: Into<PathBuf>>(&self, file_name: P) -> String {
+        let file_name = file_name.into();
+        let key = file_name.to_string_lossy();
+        match self.module_names.get(key.as_ref()) {
+            Some(name) => name.clone(),
+            None => to_module_name(file_name),
+        }
+    }
+
+    /// Proto package of a module, the module name behind the package prefix.
+    pub fn proto_package(&self, module: &str) -> String {
+        package_name(self.proto_package_prefix.as_deref(), module)
+    }
+
+    /// Add the configured attributes to a prost_build config.
+    pub fn apply_prost(&self, prost_config: &mut prost_build::Config) {
+        if !self.proto_comments {
+            prost_config.disable_comments(["."]);
+        }
+        for (path, attribute) in &self.type_attributes {
+            prost_config.type_attribute(path, attribute);
+        }
+        for (path, attribute) in &self.field_attributes {
+            prost_config.field_attribute(path, attribute);
+        }
+        if let Some(hook) = self.prost_hook {
+            hook(prost_config);
+        }
+    }
+}
+
+fn package_name(prefix: Option<&str>, module: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}.{}", prefix, module),
+        None => module.to_string(),
+    }
+}
+
+fn get_ident(table: &Value, key: &str) -> Result<Option<String>, String> {
+    match table.get(key) {
+        Some(value) => as_ident(value, key).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// A string that has to be usable as a module name.
+fn as_ident(value: &Value, key: &str) -> Result<String, String> {
+    let s = value
+        .as_str()
+        .ok_or_else(|| format!("{} must be a string", key))?;
+    let mut chars = s.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if valid {
+        Ok(s.to_string())
+    } else {
+        Err(format!("{:?} is not a valid module name", s))
+    }
+}
+
+/// `group = ["ENTRY_NAME", ...]` pairs of one enum.
+fn bitmask_groups(enum_name: &str, groups: &Value) -> Result<Vec<(String, Vec<String>)>, String> {
+    let groups = groups
+        .as_table()
+        .ok_or_else(|| format!("bitmask_groups.{} must be a table", enum_name))?;
+    let mut out = vec![];
+    for (group, entries) in groups {
+        let group_name = as_ident(&Value::String(group.clone()), group)?;
+        let entries = entries
+            .as_array()
+            .and_then(|entries| {
+                entries
+                    .iter()
+                    .map(|e| e.as_str().map(|e| e.to_string()))
+                    .collect::<Option<Vec<String>>>()
+            })
+            .ok_or_else(|| {
+                format!(
+                    "bitmask_groups.{}.{} must be a list of entry names",
+                    enum_name, group
+                )
+            })?;
+        out.push((group_name, entries));
+    }
+    Ok(out)
+}
+
+fn get_strings(table: &Value, key: &str) -> Result<Option<Vec<String>>, String> {
+    match table.get(key) {
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|v| v.as_str().map(|s| s.to_string()))
+            .collect::<Option<Vec<String>>>()
+            .map(Some)
+            .ok_or_else(|| format!("{} must be a list of strings", key)),
+        Some(_) => Err(format!("{} must be a list of strings", key)),
+        None => Ok(None),
+    }
+}
+
+fn get_bool(table: &Value, key: &str) -> Result<Option<bool>, String> {
+    match table.get(key) {
+        Some(Value::Boolean(b)) => Ok(Some(*b)),
+        Some(_) => Err(format!("{} must be true or false", key)),
+        None => Ok(None),
+    }
+}
+
+/// Read a list of `{ dialect, path, attribute }` tables.  When a dialect is
+/// given the path is relative to that dialect's proto package, otherwise it
+/// is a fully qualified prost path ("." matches every type).
+fn prost_attributes(
+    prost: &Value,
+    key: &str,
+    package_prefix: Option<&str>,
+) -> Result<Vec<(String, String)>, String> {
+    let entries = match prost.get(key) {
+        Some(Value::Array(entries)) => entries,
+        Some(_) => return Err(format!("prost.{} must be an array of tables", key)),
+        None => return Ok(vec![]),
+    };
+    let mut attributes = vec![];
+    for entry in entries {
+        let get = |name: &str| entry.get(name).and_then(|v| v.as_str());
+        let attribute =
+            get("attribute").ok_or_else(|| format!("prost.{} entry is missing attribute", key))?;
+        let path = get("path").unwrap_or("");
+        let path = match get("dialect").map(|dialect| package_name(package_prefix, dialect)) {
+            Some(package) if path.is_empty() || path == "." => format!(".{}", package),
+            Some(package) => format!(".{}.{}", package, path.trim_start_matches('.')),
+            None if path.is_empty() => ".".to_string(),
+            None => path.to_string(),
+        };
+        attributes.push((path, attribute.to_string()));
+    }
+    Ok(attributes)
+}
@@ -0,0 +1,138 @@
+//! The C header of the `ffi` module of the generated crate.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// File name of the header in `include/`.
+pub const HEADER_FILE: &str = "proto_mav.h";
+
+/// Return codes of the C API as (name, value, description), the `#define`s
+/// of the header and the consts of the `ffi` module are both written from it.
+pub const RETURN_CODES: &[(&str, i32, &str)] = &[
+    ("PROTO_MAV_ERR_NULL", -1, ""),
+    (
+        "PROTO_MAV_ERR_INCOMPLETE",
+        -2,
+        "The buffer ends before the frame does.",
+    ),
+    (
+        "PROTO_MAV_ERR_MAGIC",
+        -3,
+        "The buffer does not start with a MAVLink 1 or 2 magic byte.",
+    ),
+    ("PROTO_MAV_ERR_CRC", -4, ""),
+    ("PROTO_MAV_ERR_UNKNOWN_MESSAGE", -5, ""),
+    ("PROTO_MAV_ERR_BUFFER_TOO_SMALL", -6, ""),
+    (
+        "PROTO_MAV_ERR_INVALID",
+        -7,
+        "Version not 1 or 2, or a message id too large for the version.",
+    ),
+    (
+        "PROTO_MAV_LOSSY",
+        1,
+        "Returned by proto_mav_convert when non-zero extension fields were dropped.",
+    ),
+];
+
+/// Fields of `ProtoMavFrame` as (name, Rust type, array length, description),
+/// shared by the header and the `#[repr(C)]` struct of the `ffi` module.
+pub const FRAME_FIELDS: &[(&str, &str, Option<usize>, &str)] = &[
+    ("version", "u8", None, "1 or 2."),
+    ("sequence", "u8", None, ""),
+    ("system_id", "u8", None, ""),
+    ("component_id", "u8", None, ""),
+    ("message_id", "u32", None, ""),
+    ("payload_len", "u8", None, ""),
+    ("payload", "u8", Some(255), ""),
+];
+
+const HEADER_START: &str = r#"/* This file was automatically generated, do not edit */
+#ifndef PROTO_MAV_H
+#define PROTO_MAV_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+"#;
+
+const HEADER_END: &str = r#"
+/* Parse the frame at the start of buf into out_frame.  Returns the length of
+ * the frame (signature included), or a negative PROTO_MAV_ERR_*. */
+int32_t proto_mav_parse(const uint8_t *buf, size_t len, ProtoMavFrame *out_frame);
+
+/* Write frame to buf (unsigned, MAVLink 2 payloads without their trailing
+ * zeros).  Returns the number of bytes written, or a negative PROTO_MAV_ERR_*. */
+int32_t proto_mav_serialize(const ProtoMavFrame *frame, uint8_t *buf, size_t cap);
+
+/* Convert frame to MAVLink version in place, cutting off the extension fields
+ * for MAVLink 1.  Returns 0, PROTO_MAV_LOSSY if an extension field was not
+ * zero, or a negative PROTO_MAV_ERR_*. */
+int32_t proto_mav_convert(ProtoMavFrame *frame, uint8_t version);
+
+/* Id of the message named name, as "SysStatus" or "SYS_STATUS".  Negative
+ * PROTO_MAV_ERR_* if there is no such message. */
+int32_t proto_mav_message_id(const char *name);
+
+/* Write the name of message id ("SysStatus") NUL terminated to buf.  Returns
+ * its length without the NUL, or a negative PROTO_MAV_ERR_*. */
+int32_t proto_mav_message_name(uint32_t id, char *buf, size_t cap);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PROTO_MAV_H */
+"#;
+
+/// C type of a `FRAME_FIELDS` type.
+fn c_type(rust_type: &str) -> &'static str {
+    match rust_type {
+        "u8" => "uint8_t",
+        "u32" => "uint32_t",
+        _ => panic!("no C type for {}", rust_type),
+    }
+}
+
+/// The header, the return codes and `ProtoMavFrame` between the fixed parts.
+fn header() -> String {
+    let mut header = HEADER_START.to_string();
+    writeln!(header).unwrap();
+    for (name, value, description) in RETURN_CODES {
+        if !description.is_empty() {
+            writeln!(header, "/* {} */", description).unwrap();
+        }
+        writeln!(header, "#define {} {}", name, value).unwrap();
+    }
+    writeln!(header).unwrap();
+    writeln!(
+        header,
+        "/* One MAVLink frame, the payload as on the wire. */"
+    )
+    .unwrap();
+    writeln!(header, "typedef struct ProtoMavFrame {{").unwrap();
+    for (name, rust_type, len, description) in FRAME_FIELDS {
+        if !description.is_empty() {
+            writeln!(header, "  /* {} */", description).unwrap();
+        }
+        match len {
+            Some(len) => writeln!(header, "  {} {}[{}];", c_type(rust_type), name, len),
+            None => writeln!(header, "  {} {};", c_type(rust_type), name),
+        }
+        .unwrap();
+    }
+    writeln!(header, "}} ProtoMavFrame;").unwrap();
+    header.push_str(HEADER_END);
+    header
+}
+
+/// Write the header of the C API to `include/` of the crate at `out_dir`.
+pub fn generate(out_dir: &Path) {
+    let include_out = out_dir.join("include");
+    fs::create_dir_all(&include_out).unwrap();
+    fs::write(include_out.join(HEADER_FILE), header()).unwrap();
+}
@@ -0,0 +1,160 @@
+//! A cargo-fuzz crate for the generated code, one target per dialect feeding
+//! arbitrary bytes to the MAVLink and protobuf parsers.
+
+use std::fs;
+use std::path::Path;
+
+use crate::util::ident;
+
+/// `fuzz/Cargo.toml`, cargo-fuzz expects its own workspace.
+fn manifest(crate_name: &str, modules: &[String]) -> String {
+    let mut manifest = format!(
+        r#"[package]
+name = "{crate_name}-fuzz"
+version = "0.0.0"
+publish = false
+edition = "2018"
+
+[package.metadata]
+cargo-fuzz = true
+
+[dependencies]
+libfuzzer-sys = "0.4"
+proto_mav_comm = {{ git="https://github.com/eucleo/proto-mav-comm.git" }}
+{crate_name} = {{ path = ".." }}
+
+[workspace]
+members = ["."]
+"#,
+        crate_name = crate_name
+    );
+    for module in modules {
+        manifest.push_str(&format!(
+            r#"
+[[bin]]
+name = "{module}"
+path = "fuzz_targets/{module}.rs"
+test = false
+doc = false
+"#,
+            module = module
+        ));
+    }
+    manifest
+}
+
+/// The first bytes pick the MAVLink version and a message of the dialect,
+/// the rest is its payload.  The whole input is also read as a frame.
+/// Written as text, the formatter leaves the macro body alone.
+fn target(crate_name: &str, mavlink_module: &str, module: &str) -> String {
+    format!(
+        r#"// This file was automatically generated, do not edit
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use proto_mav_comm::{{MavlinkVersion, Message}};
+use {crate_path}::{mavlink_module}::{module}::{{MavMessage, MESSAGES}};
+
+fuzz_target!(|data: &[u8]| {{
+    if data.len() >= 3 && !MESSAGES.is_empty() {{
+        let version = if data[0] & 1 == 0 {{
+            MavlinkVersion::V1
+        }} else {{
+            MavlinkVersion::V2
+        }};
+        let index = usize::from(u16::from_le_bytes([data[1], data[2]]));
+        let id = MESSAGES[index % MESSAGES.len()].id;
+        let _ = MavMessage::parse(version, id, &data[3..]);
+        let _ = MavMessage::proto_parse(id, &data[3..]);
+    }}
+    let mut frame = data;
+    let _: Result<(_, MavMessage), _> = proto_mav_comm::read_v2_msg(&mut frame);
+}});
+"#,
+        crate_path = crate_name.replace('-', "_"),
+        mavlink_module = mavlink_module,
+        module = ident(module),
+    )
+}
+
+/// Write `fuzz/` with a target for every dialect in `modules`.
+pub fn generate(out_dir: &Path, crate_name: &str, mavlink_module: &str, modules: &[String]) {
+    let fuzz_out = out_dir.join("fuzz");
+    let targets_out = fuzz_out.join("fuzz_targets");
+    // start empty, an earlier run may have had more dialects
+    let _ = fs::remove_dir_all(&targets_out);
+    fs::create_dir_all(&targets_out).unwrap();
+    fs::write(fuzz_out.join("Cargo.toml"), manifest(crate_name, modules)).unwrap();
+    fs::write(fuzz_out.join(".gitignore"), "target\ncorpus\nartifacts\n").unwrap();
+    for module in modules {
+        fs::write(
+            targets_out.join(format!("{}.rs", module)),
+            target(crate_name, mavlink_module, module),
+        )
+        .unwrap();
+    }
+}
+
+/// Drop the `fuzz/` of an earlier run that had fuzz targets enabled.
+pub fn remove(out_dir: &Path) {
+    let _ = fs::remove_dir_all(out_dir.join("fuzz"));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn modules() -> Vec<String> {
+        vec!["common".to_string(), "ardupilotmega".to_string()]
+    }
+
+    #[test]
+    fn test_targets_parse() {
+        for module in modules() {
+            let code = target("proto-mav-gen", "mavlink", &module);
+            let file = syn::parse_file(&code)
+                .unwrap_or_else(|e| panic!("fuzz target {} does not parse: {}", module, e));
+            let fuzz_target = file
+                .items
+                .iter()
+                .find_map(|item| match item {
+                    syn::Item::Macro(item) if item.mac.path.is_ident("fuzz_target") => {
+                        Some(&item.mac)
+                    }
+                    _ => None,
+                })
+                .expect("no fuzz_target! invocation");
+            // the macro body is only tokens to syn::parse_file
+            fuzz_target
+                .parse_body::<syn::ExprClosure>()
+                .unwrap_or_else(|e| panic!("fuzz target {} body does not parse: {}", module, e));
+        }
+    }
+
+    #[test]
+    fn test_manifest_parses() {
+        let manifest: toml::Value = manifest("proto-mav-gen", &modules()).parse().unwrap();
+        let bins = manifest["bin"].as_array().unwrap();
+        let names: Vec<&str> = bins
+            .iter()
+            .map(|bin| bin["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["common", "ardupilotmega"]);
+        assert!(manifest["dependencies"].get("proto-mav-gen").is_some());
+    }
+
+    #[test]
+    fn test_generate_and_remove() {
+        let out_dir = std::env::temp_dir().join(format!("proto-mav-fuzz-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&out_dir);
+        let targets = out_dir.join("fuzz/fuzz_targets");
+        generate(&out_dir, "proto_mav_gen", "mavlink", &modules());
+        assert!(targets.join("ardupilotmega.rs").exists());
+        // a later run with fewer dialects drops the stale targets
+        generate(&out_dir, "proto_mav_gen", "mavlink", &modules()[..1]);
+        assert!(targets.join("common.rs").exists());
+        assert!(!targets.join("ardupilotmega.rs").exists());
+        remove(&out_dir);
+        assert!(!out_dir.join("fuzz").exists());
+        let _ = fs::remove_dir_all(&out_dir);
+    }
+}
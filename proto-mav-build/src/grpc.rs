@@ -0,0 +1,53 @@
+//! The `mav_stream` gRPC service, streaming MAVLink messages of any dialect
+//! in a `MavFrame` envelope.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use crate::config::BuildConfig;
+use crate::util::write_generated;
+
+/// File name of the service definition next to the dialect proto files.
+pub const PROTO_FILE: &str = "mav_stream.proto";
+
+/// The service definition after its `package` line.
+const PROTO: &str = r#"
+// One MAVLink message.
+message MavFrame {
+  // Dialect module the message belongs to, e.g. "common".
+  string dialect = 1;
+  uint32 system_id = 2;
+  uint32 component_id = 3;
+  uint32 message_id = 4;
+  // The message struct encoded as protobuf (not the MAVLink payload).
+  bytes payload = 5;
+}
+
+service MavlinkStream {
+  // Both sides send frames as they come.
+  rpc Stream(stream MavFrame) returns (stream MavFrame);
+}
+"#;
+
+/// Write the service definition to `protobufs_out` and generate the tonic
+/// client and server (`mav_stream.rs`) into `proto_out`.
+pub fn generate(protobufs_out: &Path, proto_out: &Path, config: &BuildConfig) {
+    let package = config.proto_package("mav_stream");
+    let proto_path = protobufs_out.join(PROTO_FILE);
+    let mut outf = File::create(&proto_path).unwrap();
+    writeln!(outf, "\nsyntax = \"proto3\";\n\npackage {};", package).unwrap();
+    outf.write_all(PROTO.as_bytes()).unwrap();
+
+    tonic_build::configure()
+        .out_dir(proto_out)
+        .compile(&[proto_path], &[protobufs_out])
+        .unwrap();
+    // tonic names the file after the package
+    let rs_path = proto_out.join("mav_stream.rs");
+    let package_rs = proto_out.join(format!("{}.rs", package));
+    if package_rs != rs_path {
+        fs::rename(&package_rs, &rs_path).unwrap();
+    }
+    write_generated(&rs_path, &fs::read(&rs_path).unwrap());
+}
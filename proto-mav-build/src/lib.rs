@@ -0,0 +1,387 @@
+//! The MAVLink XML compiler behind proto_mav_gen, usable from other build
+//! scripts to generate custom dialects.
+//!
+//! For every dialect (and every dialect it includes) this writes a proto
+//! file to `protos/`, the prost structs to `src/proto/` and the MAVLink
+//! (de)serialization code to `src/mavlink/` of a crate directory, plus the
+//! `src/lib.rs` tying them together.  The generated code refers to itself
+//! with `crate::` paths, so `out_dir` has to be the root of its own crate,
+//! depending on `proto_mav_comm`, `prost`, `bytes`, `num-traits`,
+//! `num-derive`, `bitflags` and `serde` like proto_mav_gen does.
+//!
+//! ```no_run
+//! let options = proto_mav_build::Options::default()
+//!     .include_dir("mavlink/message_definitions/v1.0");
+//! proto_mav_build::generate("dialects/my_dialect.xml", "my-dialect-gen", &options);
+//! ```
+//!
+//! The prost_build config can be customized beyond the attributes of the
+//! config file with [`BuildConfig::prost_hook`].  It is a plain `fn`, so it
+//! can not capture anything, settings have to be constants:
+//!
+//! ```no_run
+//! let mut config = proto_mav_build::BuildConfig::default();
+//! config.prost_hook = Some(|prost_config| {
+//!     prost_config.type_attribute(".common.Heartbeat", "#[doc(alias = \"HEARTBEAT\")]");
+//! });
+//! ```
+//!
+//! The generated MAVLink code relies on the prost field types, the hook must
+//! not change them (e.g. `bytes` to `bytes::Bytes`).
+#![recursion_limit = "256"]
+#[macro_use]
+extern crate quote;
+
+extern crate xml;
+
+mod binder;
+mod config;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod fuzz;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod mavlink;
+mod parser;
+mod proto;
+mod schema;
+mod util;
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{read_dir, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+
+pub use crate::config::{BuildConfig, CONFIG_ENV};
+
+/// Options for [`generate`], built from `Options::default()`.  More
+/// options may be added, so it can not be built as a struct literal.
+#[derive(Debug, Clone, Default)]
+#[non_exhaustive]
+pub struct Options {
+    /// Layout, prost attributes and message filter, usually read from a
+    /// `proto-mav.toml` with [`BuildConfig::load`].
+    pub config: BuildConfig,
+    /// Directories searched for included definitions (e.g. `common.xml`)
+    /// after the directory of the dialect itself.
+    pub include_dirs: Vec<PathBuf>,
+}
+
+impl Options {
+    /// Generate with `config` instead of the default configuration.
+    pub fn config(mut self, config: BuildConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Add a directory to search for included definitions.
+    pub fn include_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.include_dirs.push(dir.into());
+        self
+    }
+}
+
+/// Generate the dialect `xml_path` and the dialects it includes into the
+/// crate at `out_dir`.
+pub fn generate<P: AsRef<Path>, Q: AsRef<Path>>(xml_path: P, out_dir: Q, options: &Options) {
+    let xml_path = xml_path.as_ref();
+    let definition_file = xml_path
+        .file_name()
+        .unwrap_or_else(|| panic!("{} is not a definition file", xml_path.display()));
+    let mut definitions_dirs = vec![xml_path
+        .parent()
+        .map_or_else(PathBuf::new, Path::to_path_buf)];
+    definitions_dirs.extend(options.include_dirs.iter().cloned());
+
+    generate_files(
+        &definitions_dirs,
+        vec![definition_file.to_os_string()],
+        out_dir.as_ref(),
+        &options.config,
+    );
+}
+
+/// Generate every dialect found in `definitions_dirs` into the crate at
+/// `out_dir`.  Includes are looked up in all directories, in order, and
+/// module names have to be unique.
+pub fn generate_dirs<Q: AsRef<Path>>(
+    definitions_dirs: &[PathBuf],
+    out_dir: Q,
+    config: &BuildConfig,
+) {
+    generate_dirs_filtered(definitions_dirs, out_dir, config, |_| true);
+}
+
+/// Like [`generate_dirs`], but only for the XML files `filter` accepts (by
+/// path), this is how proto_mav_gen is built.  The dialects they include
+/// are generated as well.
+pub fn generate_dirs_filtered<Q: AsRef<Path>, F: Fn(&Path) -> bool>(
+    definitions_dirs: &[PathBuf],
+    out_dir: Q,
+    config: &BuildConfig,
+    filter: F,
+) {
+    let mut modules = vec![];
+    let mut definition_files = vec![];
+    for definitions_dir in definitions_dirs {
+        // read_dir order differs between file systems, the module order
+        // ends up in the generated code
+        let mut entries = read_dir(definitions_dir)
+            .expect("could not read definitions directory")
+            .map(|entry| entry.expect("could not read directory entry"))
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            if entry.path().extension().and_then(|ext| ext.to_str()) != Some("xml")
+                || !filter(&entry.path())
+            {
+                continue;
+            }
+
+            let definition_file = entry.file_name();
+            let module_name = config.module_name(&definition_file);
+            if modules.contains(&module_name) {
+                panic!(
+                    "{} in {} clashes with another dialect named {}",
+                    definition_file.to_string_lossy(),
+                    definitions_dir.display(),
+                    module_name
+                );
+            }
+
+            modules.push(module_name);
+            definition_files.push(definition_file);
+        }
+    }
+
+    generate_files(definitions_dirs, definition_files, out_dir.as_ref(), config);
+}
+
+fn generate_files(
+    definitions_dirs: &[PathBuf],
+    mut definition_files: Vec<OsString>,
+    out_dir: &Path,
+    config: &BuildConfig,
+) {
+    let mav_out = out_dir.join("src").join("mavlink");
+    let proto_out = out_dir.join("src").join("proto");
+    let protobufs_out = out_dir.join("protos");
+    // start empty, an earlier run may have generated more dialects
+    for dir in [&mav_out, &proto_out, &protobufs_out] {
+        let _ = std::fs::remove_dir_all(dir);
+        std::fs::create_dir_all(dir).unwrap();
+    }
+    #[cfg(not(feature = "ffi"))]
+    let _ = std::fs::remove_file(out_dir.join("include").join("proto_mav.h"));
+
+    let message_filter = config
+        .messages
+        .as_ref()
+        .map(|messages| parser::MessageFilter::new(messages, definitions_dirs));
+
+    let mut modules_map: HashMap<String, parser::MavProfile> = HashMap::new();
+    for definition_file in &definition_files {
+        parser::load(
+            definitions_dirs,
+            definition_file,
+            &mut modules_map,
+            message_filter.as_ref(),
+        );
+    }
+    if config.proto_u8_bytes {
+        for profile in modules_map.values_mut() {
+            profile.u8_arrays_as_bytes();
+        }
+    }
+    // the generated code needs the includes as well
+    let mut includes: Vec<&String> = modules_map
+        .keys()
+        .filter(|key| {
+            !definition_files
+                .iter()
+                .any(|file| file.to_string_lossy() == key.as_str())
+        })
+        .collect();
+    includes.sort();
+    let includes: Vec<OsString> = includes.into_iter().map(OsString::from).collect();
+    definition_files.extend(includes);
+    let modules: Vec<String> = definition_files
+        .iter()
+        .map(|file| config.module_name(file))
+        .collect();
+    // the gRPC service only has a proto module, no MAVLink code
+    #[allow(unused_mut)]
+    let mut proto_modules = modules.clone();
+    #[cfg(feature = "grpc")]
+    proto_modules.push("mav_stream".to_string());
+
+    // code generation and formatting dominate the build, run them on all cores
+    let workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(definition_files.len());
+    let pending = Mutex::new(definition_files.iter());
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                let definition_file = match pending.lock().unwrap().next() {
+                    Some(definition_file) => definition_file,
+                    None => break,
+                };
+                parser::generate(
+                    definitions_dirs,
+                    definition_file,
+                    out_dir,
+                    &modules_map,
+                    config,
+                );
+            });
+        }
+    });
+
+    if config.fuzz_targets {
+        fuzz::generate(
+            out_dir,
+            &config.fuzz_crate_name,
+            &config.mavlink_module,
+            &modules,
+        );
+    } else {
+        fuzz::remove(out_dir);
+    }
+
+    #[cfg(feature = "ffi")]
+    ffi::generate(out_dir);
+
+    // output mod.rs for src
+    {
+        let dest_path = out_dir.join("src").join("lib.rs");
+        let mut code = vec![];
+        {
+            let src_modules = vec![
+                ("mavlink".to_string(), config.mavlink_module.clone()),
+                ("proto".to_string(), config.proto_module.clone()),
+            ];
+            // generate code
+            binder::generate_bare(&src_modules, &mut code);
+            binder::generate_proto_sources(&proto_modules, &mut code);
+            #[cfg(feature = "ffi")]
+            binder::generate_ffi(&modules, &mut code);
+        }
+
+        util::write_generated(&dest_path, &code);
+    }
+
+    // output mod.rs for mavlink
+    {
+        let dest_path = Path::new(&mav_out).join("mod.rs");
+        let mut code = vec![];
+        {
+            // generate code
+            binder::generate(&modules, &mut code);
+            #[cfg(feature = "schema")]
+            binder::generate_schemas(&modules, &mut code);
+        }
+
+        util::write_generated(&dest_path, &code);
+    }
+
+    {
+        let dest_path = Path::new(&protobufs_out).join("mav.proto");
+        let mut outf = File::create(&dest_path).unwrap();
+        writeln!(
+            outf,
+            "\nsyntax = \"proto3\";\n\npackage {};",
+            config.proto_package("mav")
+        )
+        .unwrap();
+        let opts = r#"
+import "google/protobuf/descriptor.proto";
+
+// MAVLink options of the messages and fields of the dialect proto files.
+// The field numbers and the extension number 60066 do not change, readers
+// in other languages can rely on them.  60066 is in the range protobuf keeps
+// for use within an organization (50000-99999), it is not registered.
+
+// MAVLink type ("uint8_t", "char[16]"), enum ("MAV_TYPE", the full proto name
+// like "common.MAV_TYPE" for an enum of an include, with the package prefix if
+// one is configured) and display ("bitmask") of a field.
+message MavFieldOptions {
+  optional string type = 1;
+  optional string enum = 2;
+  optional string display = 3;
+  // Value range of the MAVLink type when it is narrower than the proto type.
+  optional sint64 min = 4;
+  optional sint64 max = 5;
+}
+
+// MAVLink id of a message.
+message MavMesOptions {
+  optional int32 id = 1;
+}
+
+extend google.protobuf.FieldOptions {
+  optional MavFieldOptions opts = 60066;
+}
+extend google.protobuf.MessageOptions {
+  optional MavMesOptions message = 60066;
+}
+"#;
+        outf.write_all(opts.as_bytes()).unwrap();
+    }
+    let mut protos = Vec::new();
+    for module in &modules {
+        protos.push(protobufs_out.join(format!("{}.proto", module)));
+    }
+    let mut prost_config = prost_build::Config::new();
+    prost_config
+        .out_dir(&proto_out)
+        .file_descriptor_set_path(protobufs_out.join("descriptor_set.bin"))
+        //        .compile_well_known_types()
+        .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]");
+    config.apply_prost(&mut prost_config);
+    let mut loaded = modules_map
+        .iter()
+        .collect::<Vec<(&String, &parser::MavProfile)>>();
+    loaded.sort_by_key(|(definition_file, _)| *definition_file);
+    for (definition_file, profile) in loaded {
+        let package = config.proto_package(&config.module_name(definition_file));
+        profile.apply_deprecations(&package, &mut prost_config);
+    }
+    prost_config
+        .compile_protos(&protos, &[&protobufs_out])
+        .unwrap();
+    // prost names the files after the packages, the modules keep the
+    // module names
+    if config.proto_package_prefix.is_some() {
+        for module in modules
+            .iter()
+            .map(|module| module.as_str())
+            .chain(Some("mav"))
+        {
+            let package_rs = proto_out.join(format!("{}.rs", config.proto_package(module)));
+            if package_rs.exists() {
+                std::fs::rename(&package_rs, proto_out.join(format!("{}.rs", module))).unwrap();
+            }
+        }
+    }
+    #[cfg(feature = "grpc")]
+    grpc::generate(&protobufs_out, &proto_out, config);
+
+    // output mod.rs for proto
+    {
+        let dest_path = Path::new(&proto_out).join("mod.rs");
+        let mut code = vec![];
+        {
+            // generate code
+            binder::generate(&proto_modules, &mut code);
+            binder::generate_descriptor_set(&mut code);
+            #[cfg(feature = "grpc")]
+            binder::generate_grpc(&mut code);
+        }
+
+        util::write_generated(&dest_path, &code);
+    }
+}
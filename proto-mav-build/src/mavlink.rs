@@ -0,0 +1,2704 @@
+use std::collections::HashMap;
+
+use crc_any::CRCu16;
+use proc_macro2::{Ident, Literal, TokenStream};
+
+use crate::config::BuildConfig;
+use crate::parser::*;
+use crate::util::ident;
+
+/// CRC operates over names of the message and names of its fields.
+/// Hence we have to preserve the original XML names.
+fn extra_crc(msg: &MavMessage) -> u8 {
+    // calculate a 8-bit checksum of the key fields of a message, so we
+    // can detect incompatible XML changes
+    let mut crc = CRCu16::crc16mcrf4cc();
+
+    crc.digest(msg.raw_name.as_bytes());
+    crc.digest(" ".as_bytes());
+
+    let mut f = msg.fields.clone();
+    // only mavlink 1 fields should be part of the extra_crc
+    f.retain(|f| !f.is_extension);
+    f.sort_by(|a, b| a.mavtype.compare(&b.mavtype));
+    for field in &f {
+        crc.digest(field.mavtype.primitive_type().as_bytes());
+        crc.digest(" ".as_bytes());
+        crc.digest(field.raw_name.as_bytes());
+        crc.digest(" ".as_bytes());
+        if let MavType::Array(_, size) = field.mavtype {
+            crc.digest(&[size as u8]);
+        }
+    }
+
+    let crcval = crc.get_crc();
+    ((crcval & 0xFF) ^ (crcval >> 8)) as u8
+}
+
+/// Accessor suffix, SI unit (degrees for angles) and scale of the scaled
+/// MAVLink units.
+#[cfg(feature = "emit-units")]
+fn si_scale(units: &str) -> Option<(&'static str, &'static str, f64)> {
+    Some(match units {
+        "degE7" => ("deg", "deg", 1e-7),
+        "degE5" => ("deg", "deg", 1e-5),
+        "cdeg" => ("deg", "deg", 1e-2),
+        "cdeg/s" => ("deg_s", "deg/s", 1e-2),
+        "mrad/s" => ("rad_s", "rad/s", 1e-3),
+        "mm" => ("m", "m", 1e-3),
+        "cm" => ("m", "m", 1e-2),
+        "dm" => ("m", "m", 1e-1),
+        "dam" => ("m", "m", 1e1),
+        "mm/s" => ("m_s", "m/s", 1e-3),
+        "cm/s" => ("m_s", "m/s", 1e-2),
+        "dm/s" => ("m_s", "m/s", 1e-1),
+        "mG" => ("m_s2", "m/s^2", 9.80665e-3),
+        "mgauss" => ("t", "T", 1e-7),
+        "cdegC" => ("degc", "degC", 1e-2),
+        "mV" => ("v", "V", 1e-3),
+        "mA" => ("a", "A", 1e-3),
+        "cA" => ("a", "A", 1e-2),
+        "mbar" => ("pa", "Pa", 1e2),
+        "hJ" => ("j", "J", 1e2),
+        "us" => ("s", "s", 1e-6),
+        "ms" => ("s", "s", 1e-3),
+        "cs" => ("s", "s", 1e-2),
+        "ds" => ("s", "s", 1e-1),
+        _ => return None,
+    })
+}
+
+impl MavProfile {
+    /// Emit includes
+    fn emit_includes(&self, config: &BuildConfig) -> Vec<Ident> {
+        self.includes
+            .iter()
+            .map(|i| ident(&config.module_name(i)))
+            .collect::<Vec<Ident>>()
+    }
+
+    /// Emit rust messages
+    fn emit_msgs(&self, module_name: &str, config: &BuildConfig) -> Vec<TokenStream> {
+        self.messages
+            .iter()
+            .map(|d| d.emit_rust(module_name, config))
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// Get list of original message names
+    fn emit_enum_names(&self) -> Vec<TokenStream> {
+        self.messages
+            .iter()
+            .map(|msg| {
+                let name = ident(&msg.name);
+                quote!(#name)
+            })
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// Emit message names with "_DATA" at the end
+    fn emit_struct_names(&self, module_name: &str) -> Vec<TokenStream> {
+        self.messages
+            .iter()
+            .map(|msg| msg.emit_struct_name(module_name))
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// A list of message IDs
+    fn emit_msg_ids(&self) -> Vec<TokenStream> {
+        self.messages
+            .iter()
+            .map(|msg| {
+                let id = Literal::u32_unsuffixed(msg.id);
+                quote!(#id)
+            })
+            .collect::<Vec<TokenStream>>()
+    }
+
+    pub fn emit_rust(
+        &self,
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
+    ) -> TokenStream {
+        //TODO verify that id_width of u8 is OK even in mavlink v1
+        let id_width = quote!(u32);
+
+        let msgs = self.emit_msgs(module_name, config);
+        let includes = self.emit_includes(config);
+        let enum_names = self.emit_enum_names();
+        let struct_names = self.emit_struct_names(module_name);
+        let msg_ids = self.emit_msg_ids();
+
+        let mav_message = self.emit_mav_message(&enum_names, &struct_names, &includes);
+        let mav_message_from_includes = self.emit_mav_message_from_includes(&includes);
+        let mav_message_parse = self.emit_mav_message_parse(
+            &enum_names,
+            &struct_names,
+            &msg_ids,
+            &includes,
+            module_name,
+            modules,
+        );
+        let mav_message_proto_parse = self.emit_mav_message_proto_parse(
+            &enum_names,
+            &struct_names,
+            &msg_ids,
+            &includes,
+            module_name,
+            modules,
+        );
+        let mav_message_crc = self.emit_mav_message_crc(modules);
+        let mav_message_name = self.emit_mav_message_name(&enum_names, &includes);
+        let mav_message_id = self.emit_mav_message_id(&enum_names, &msg_ids, &includes);
+        let mav_message_id_from_name = self.emit_mav_message_id_from_name(modules);
+        let mav_message_from_name = self.emit_mav_message_from_name(modules);
+        let mav_message_default_from_id = self.emit_mav_message_default_from_id(
+            &enum_names,
+            &msg_ids,
+            &includes,
+            module_name,
+            modules,
+        );
+        let mav_message_unset_from_id = self.emit_mav_message_unset_from_id(
+            &enum_names,
+            &msg_ids,
+            &includes,
+            module_name,
+            modules,
+        );
+        let mav_message_serialize = self.emit_mav_message_serialize(&enum_names, &includes);
+        let mav_message_serialize_into =
+            self.emit_mav_message_serialize_into(&enum_names, &includes);
+        let mav_message_proto_encode = self.emit_proto_message_serialize(&enum_names, &includes);
+        let mav_message_kind = self.emit_mav_message_kind(&enum_names, &includes, modules, config);
+        let mav_message_target = self.emit_mav_message_target(&includes);
+        let bitmask_helpers = self.emit_bitmask_helpers(module_name, config);
+        let enum_mav_names = self.emit_enum_mav_names(module_name);
+        let builders = self.emit_builders(module_name, modules, config);
+        let enum_bridges = self.emit_enum_bridges(module_name, modules, config);
+        let prelude = self.emit_prelude(module_name, modules, config);
+        let commands = self.emit_commands(module_name, modules, config);
+        #[cfg(feature = "schema")]
+        let schema = {
+            let schema_doc = format!(
+                " JSON schema of the messages of this dialect, `protos/{}.schema.json`.",
+                module_name
+            );
+            let schema_path = format!("../../protos/{}.schema.json", module_name);
+            quote! {
+                #[doc = #schema_doc]
+                pub const SCHEMA: &str = include_str!(#schema_path);
+            }
+        };
+        #[cfg(not(feature = "schema"))]
+        let schema = quote!();
+        let dialect_spec = self.emit_dialect_spec(
+            module_name,
+            &struct_names,
+            &msg_ids,
+            &includes,
+            modules,
+            config,
+        );
+
+        quote! {
+            use proto_mav_comm::MavlinkVersion;
+            #[allow(unused_imports)]
+            use bytes::{Buf, BufMut, Bytes, BytesMut};
+            #[allow(unused_imports)]
+            use num_derive::FromPrimitive;
+            #[allow(unused_imports)]
+            use num_traits::FromPrimitive;
+            #[allow(unused_imports)]
+            use num_derive::ToPrimitive;
+            #[allow(unused_imports)]
+            use num_traits::ToPrimitive;
+            #[allow(unused_imports)]
+            use bitflags::bitflags;
+            #[allow(unused_imports)]
+            use prost::Message as ProstMessage;
+
+            use proto_mav_comm::{Message, error::*};
+            #[allow(unused_imports)]
+            use crate::{#(mavlink::#includes::*),*};
+
+            #(#msgs)*
+
+            #(#bitmask_helpers)*
+
+            #(#enum_mav_names)*
+
+            #(#builders)*
+
+            #enum_bridges
+
+            #prelude
+
+            #commands
+
+            #schema
+
+            #[derive(Clone, PartialEq, Debug)]
+            #mav_message
+
+            #mav_message_from_includes
+
+            #mav_message_unset_from_id
+
+            impl MavMessage {
+                #mav_message_serialize_into
+            }
+
+            #mav_message_crc
+
+            #mav_message_from_name
+
+            #mav_message_kind
+
+            #mav_message_target
+
+            #dialect_spec
+
+            impl Message for MavMessage {
+                #mav_message_parse
+                #mav_message_proto_parse
+                #mav_message_name
+                #mav_message_id
+                #mav_message_id_from_name
+                #mav_message_default_from_id
+                #mav_message_serialize
+                #mav_message_proto_encode
+
+                fn extra_crc(id: #id_width) -> u8 {
+                    MavMessage::lookup_extra_crc(id).unwrap_or(0)
+                }
+            }
+        }
+    }
+
+    /// Helpers to decode bitmask values into the entries of their enum, plus
+    /// the groups of entries defined in the build config.
+    fn emit_bitmask_helpers(&self, module_name: &str, config: &BuildConfig) -> Vec<TokenStream> {
+        let module_ident = ident(module_name);
+        self.enums
+            .iter()
+            .filter(|e| e.bitfield.is_some())
+            .map(|e| {
+                let enum_ident = ident(&e.name);
+                let bits = e
+                    .entries
+                    .iter()
+                    .filter(|entry| matches!(entry.value, Some(v) if v.is_power_of_two()))
+                    .collect::<Vec<&MavEnumEntry>>();
+                let bit_names = bits
+                    .iter()
+                    .filter(|entry| e.alias_of(entry).is_none())
+                    .map(|entry| ident(&entry.name))
+                    .collect::<Vec<Ident>>();
+
+                let groups = config
+                    .bitmask_groups
+                    .get(&e.raw_name)
+                    .map(|groups| groups.as_slice())
+                    .unwrap_or(&[])
+                    .iter()
+                    .filter_map(|(group, members)| {
+                        let mut mask: u32 = 0;
+                        let mut names = vec![];
+                        for member in members {
+                            match bits.iter().find(|entry| entry.raw_name == *member) {
+                                Some(entry) => {
+                                    mask |= entry.value.unwrap();
+                                    names.push(ident(&entry.name));
+                                }
+                                None => {
+                                    println!(
+                                        "cargo:warning=bitmask group {}.{}: {} is not a bit of {} in {}, group skipped",
+                                        e.raw_name, group, member, e.raw_name, module_name
+                                    );
+                                    return None;
+                                }
+                            }
+                        }
+                        let mask_name = ident(&group.to_uppercase());
+                        let list_name = ident(group);
+                        let has_name = format_ident!("has_{}", group);
+                        Some(quote! {
+                            pub const #mask_name: u32 = #mask;
+
+                            pub fn #list_name() -> &'static [Self] {
+                                &[#(Self::#names),*]
+                            }
+
+                            /// True when every bit of the group is set in `value`.
+                            pub fn #has_name(value: u32) -> bool {
+                                value & Self::#mask_name == Self::#mask_name
+                            }
+                        })
+                    })
+                    .collect::<Vec<TokenStream>>();
+
+                quote! {
+                    impl crate::proto::#module_ident::#enum_ident {
+                        /// All single bit entries of this bitmask.
+                        pub const ALL_BITS: &'static [Self] = &[#(Self::#bit_names),*];
+
+                        pub fn bit(self) -> u32 {
+                            self as i32 as u32
+                        }
+
+                        pub fn is_set(self, value: u32) -> bool {
+                            value & self.bit() != 0
+                        }
+
+                        /// The entries whose bit is set in `value`.
+                        pub fn iter_set_bits(value: u32) -> impl Iterator<Item = Self> {
+                            Self::ALL_BITS.iter().copied().filter(move |bit| bit.is_set(value))
+                        }
+
+                        /// Bits of `value` that do not belong to any entry.
+                        pub fn unknown_bits(value: u32) -> u32 {
+                            Self::ALL_BITS.iter().fold(value, |rest, bit| rest & !bit.bit())
+                        }
+
+                        #(#groups)*
+                    }
+                }
+            })
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// A builder per message of this dialect, with a setter per field that
+    /// takes the enum for enum fields (prost stores them as i32).
+    fn emit_builders(
+        &self,
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
+    ) -> Vec<TokenStream> {
+        let module_ident = ident(module_name);
+        // the enum of a field as the proto file resolves it: this dialect
+        // first, then its direct includes
+        let find_enum = |name: &str| -> Option<(Ident, &MavEnum)> {
+            if let Some(e) = self.enums.iter().find(|e| e.name == name) {
+                return Some((module_ident.clone(), e));
+            }
+            self.includes.iter().find_map(|inc| {
+                let inc_profile = modules.get(inc)?;
+                let e = inc_profile.enums.iter().find(|e| e.name == name)?;
+                Some((ident(&config.module_name(inc)), e))
+            })
+        };
+
+        self.messages
+            .iter()
+            .map(|msg| {
+                let msg_name = msg.emit_struct_name(module_name);
+                let builder_name = format_ident!("{}Builder", msg.name);
+                let setters = msg.fields.iter().map(|f| {
+                    let name = ident(&f.name);
+                    let value_type = ident(&f.proto_scalar_type());
+                    let typed_enum = f
+                        .enumtype
+                        .as_deref()
+                        .and_then(find_enum)
+                        .filter(|(_, e)| e.bitfield.is_none())
+                        .map(|(module, e)| {
+                            let enum_ident = ident(&e.name);
+                            quote!(crate::proto::#module::#enum_ident)
+                        });
+                    let (value_type, value) = match (&f.mavtype, typed_enum) {
+                        (MavType::Array(t, _), _) if **t == MavType::Char => {
+                            (quote!(impl Into<String>), quote!(value.into()))
+                        }
+                        (MavType::Array(_, _), Some(enum_type)) => (
+                            quote!(Vec<#enum_type>),
+                            quote!(value.into_iter().map(|value| value as i32).collect()),
+                        ),
+                        (MavType::Array(_, _), None) => (quote!(Vec<#value_type>), quote!(value)),
+                        (_, Some(enum_type)) => (enum_type, quote!(value as i32)),
+                        (_, None) => (quote!(#value_type), quote!(value)),
+                    };
+                    quote! {
+                        pub fn #name(mut self, value: #value_type) -> Self {
+                            self.msg.#name = #value;
+                            self
+                        }
+                    }
+                });
+                let has_field = |name: &str| msg.fields.iter().any(|f| f.name == name);
+                let target = if has_field("target_system")
+                    && has_field("target_component")
+                    && !has_field("target")
+                {
+                    quote! {
+                        /// Set target_system and target_component.
+                        pub fn target(mut self, system: u32, component: u32) -> Self {
+                            self.msg.target_system = system;
+                            self.msg.target_component = component;
+                            self
+                        }
+                    }
+                } else {
+                    quote!()
+                };
+                let doc = format!(" Builder of `{}`, fields not set stay zero.", msg.name);
+
+                quote! {
+                    #[doc = #doc]
+                    #[derive(Clone, Debug, Default)]
+                    pub struct #builder_name {
+                        msg: #msg_name,
+                    }
+
+                    impl #msg_name {
+                        pub fn builder() -> #builder_name {
+                            #builder_name::default()
+                        }
+                    }
+
+                    impl #builder_name {
+                        #(#setters)*
+
+                        #target
+
+                        pub fn build(self) -> #msg_name {
+                            self.msg
+                        }
+                    }
+                }
+            })
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// The MAVLink (ALL_CAPS) names of the entries of this dialect's enums,
+    /// prost only knows the CamelCase names.  Entries sharing the value of
+    /// an earlier one have no variant of their own, they become constants.
+    fn emit_enum_mav_names(&self, module_name: &str) -> Vec<TokenStream> {
+        let module_ident = ident(module_name);
+        self.enums
+            .iter()
+            .map(|e| {
+                let enum_ident = ident(&e.name);
+                let mut names = e
+                    .entries
+                    .iter()
+                    .filter(|entry| e.alias_of(entry).is_none())
+                    .map(|entry| (ident(&entry.name), entry.raw_name.clone()))
+                    .collect::<Vec<(Ident, String)>>();
+                let aliases = e
+                    .entries
+                    .iter()
+                    .filter_map(|entry| Some((entry, e.alias_of(entry)?)))
+                    .collect::<Vec<(&MavEnumEntry, &MavEnumEntry)>>();
+                let alias_consts = aliases.iter().map(|(alias, first)| {
+                    let alias_ident = ident(&alias.name);
+                    let first_ident = ident(&first.name);
+                    let doc = format!(" {}, the same value as {}.", alias.raw_name, first.raw_name);
+                    quote! {
+                        #[doc = #doc]
+                        #[allow(non_upper_case_globals)]
+                        pub const #alias_ident: Self = Self::#first_ident;
+                    }
+                });
+                let alias_names = aliases.iter().map(|(alias, _)| alias.raw_name.as_str());
+                let alias_idents = aliases.iter().map(|(alias, _)| ident(&alias.name));
+                // the entry the proto file adds when there is no 0 value
+                let has_zero = e.entries.iter().any(|entry| entry.value == Some(0));
+                let max_val = e.entries.iter().filter_map(|entry| entry.value).max();
+                if !has_zero && matches!(max_val, Some(v) if v != 0) {
+                    names.insert(0, (ident("Undefined"), format!("{}_UNDEFINED", e.raw_name)));
+                }
+                let enum_name = e.raw_name.as_str();
+                let variants = names.iter().map(|(variant, _)| variant).collect::<Vec<_>>();
+                let raw_names = names
+                    .iter()
+                    .map(|(_, raw_name)| raw_name)
+                    .collect::<Vec<_>>();
+
+                quote! {
+                    impl crate::proto::#module_ident::#enum_ident {
+                        #(#alias_consts)*
+
+                        /// The MAVLink name of the entry, e.g. "MAV_STATE_ACTIVE".
+                        /// For aliases the name of the first entry of the value.
+                        pub fn mav_name(&self) -> &'static str {
+                            match self {
+                                #(Self::#variants => #raw_names,)*
+                            }
+                        }
+
+                        /// The entry with this MAVLink name.
+                        pub fn from_mav_name(name: &str) -> Option<Self> {
+                            match name {
+                                #(#raw_names => Some(Self::#variants),)*
+                                #(#alias_names => Some(Self::#alias_idents),)*
+                                _ => None,
+                            }
+                        }
+                    }
+
+                    impl std::fmt::Display for crate::proto::#module_ident::#enum_ident {
+                        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                            f.write_str(self.mav_name())
+                        }
+                    }
+
+                    impl std::str::FromStr for crate::proto::#module_ident::#enum_ident {
+                        type Err = crate::EnumNameError;
+
+                        fn from_str(name: &str) -> Result<Self, crate::EnumNameError> {
+                            Self::from_mav_name(name).ok_or_else(|| crate::EnumNameError {
+                                enum_type: #enum_name,
+                                name: name.to_string(),
+                            })
+                        }
+                    }
+                }
+            })
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// Conversions between this dialect's enums and the enums of the same
+    /// name in its includes, and an `enums` module re-exporting every enum
+    /// usable with the messages of this dialect.
+    fn emit_enum_bridges(
+        &self,
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
+    ) -> TokenStream {
+        let module_ident = ident(module_name);
+        let mut bridges = vec![];
+        let mut seen = self
+            .enums
+            .iter()
+            .map(|e| e.name.clone())
+            .collect::<Vec<String>>();
+        let own = seen.iter().map(|name| ident(name)).collect::<Vec<Ident>>();
+        let mut reexports = vec![];
+
+        for inc in self.all_includes(modules) {
+            let include = ident(&config.module_name(&inc));
+            let inc_profile = modules
+                .get(&inc)
+                .unwrap_or_else(|| panic!("Module {} not loaded!", inc));
+            let mut inc_enums = vec![];
+            for e in &inc_profile.enums {
+                let enum_ident = ident(&e.name);
+                if self.enums.iter().any(|own| own.name == e.name) {
+                    let enum_name = e.name.clone();
+                    bridges.push(quote! {
+                        impl From<crate::proto::#include::#enum_ident> for crate::proto::#module_ident::#enum_ident {
+                            fn from(value: crate::proto::#include::#enum_ident) -> Self {
+                                // the entries of included enums are merged into this one
+                                Self::from_i32(value as i32).unwrap()
+                            }
+                        }
+
+                        impl std::convert::TryFrom<crate::proto::#module_ident::#enum_ident> for crate::proto::#include::#enum_ident {
+                            type Error = ParserError;
+
+                            fn try_from(value: crate::proto::#module_ident::#enum_ident) -> Result<Self, ParserError> {
+                                Self::from_i32(value as i32).ok_or(ParserError::InvalidEnum {
+                                    enum_type: #enum_name.to_string(),
+                                    value: value as i32 as u32,
+                                })
+                            }
+                        }
+                    });
+                }
+                if !seen.contains(&e.name) {
+                    seen.push(e.name.clone());
+                    inc_enums.push(enum_ident);
+                }
+            }
+            if !inc_enums.is_empty() {
+                reexports.push(quote!(pub use crate::proto::#include::{#(#inc_enums),*};));
+            }
+        }
+        if !own.is_empty() {
+            reexports.insert(0, quote!(pub use crate::proto::#module_ident::{#(#own),*};));
+        }
+
+        quote! {
+            #(#bridges)*
+
+            /// Every enum that can be used with the messages of this dialect.
+            pub mod enums {
+                #(#reexports)*
+            }
+        }
+    }
+
+    /// The messages of this dialect and its (transitive) includes, each with
+    /// the module it is defined in.  A message id is only listed once.
+    fn dialect_messages<'a>(
+        &'a self,
+        module_name: &str,
+        modules: &'a HashMap<String, MavProfile>,
+        config: &BuildConfig,
+    ) -> Vec<(String, &'a MavMessage)> {
+        let mut messages: Vec<(String, &MavMessage)> = vec![];
+        let dialects = std::iter::once((module_name.to_string(), self)).chain(
+            self.all_includes(modules).into_iter().map(|inc| {
+                let inc_profile = modules
+                    .get(&inc)
+                    .unwrap_or_else(|| panic!("Module {} not loaded!", inc));
+                (config.module_name(&inc), inc_profile)
+            }),
+        );
+        for (dialect, profile) in dialects {
+            for msg in &profile.messages {
+                if !messages.iter().any(|(_, m)| m.id == msg.id) {
+                    messages.push((dialect.clone(), msg));
+                }
+            }
+        }
+        messages
+    }
+
+    /// Everything needed to work with the messages of a dialect, so users do
+    /// not have to mix `mavlink::` and `proto::` paths.
+    fn emit_prelude(
+        &self,
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
+    ) -> TokenStream {
+        let mut structs: Vec<(String, Vec<Ident>)> = vec![];
+        for (dialect, msg) in self.dialect_messages(module_name, modules, config) {
+            match structs.last_mut() {
+                Some((last, names)) if *last == dialect => names.push(ident(&msg.name)),
+                _ => structs.push((dialect, vec![ident(&msg.name)])),
+            }
+        }
+        let reexports = structs.iter().map(|(dialect, names)| {
+            let dialect = ident(dialect);
+            quote!(pub use crate::proto::#dialect::{#(#names),*};)
+        });
+
+        quote! {
+            /// The message enum, the message structs and enums of this dialect
+            /// and its includes, and the `Message` trait.
+            pub mod prelude {
+                pub use super::{MavMessage, MavMessageKind};
+                #[allow(unused_imports)]
+                pub use super::enums::*;
+                #(#reexports)*
+                pub use proto_mav_comm::{MavlinkVersion, Message};
+            }
+        }
+    }
+
+    /// Whether this dialect gets a `cmd` module: it or an include has
+    /// MAV_CMD and its messages include COMMAND_LONG.
+    fn has_commands(
+        &self,
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
+    ) -> bool {
+        let includes = self.all_includes(modules);
+        std::iter::once(self)
+            .chain(includes.iter().filter_map(|inc| modules.get(inc)))
+            .any(|profile| profile.enums.iter().any(|e| e.raw_name == "MAV_CMD"))
+            && self
+                .dialect_messages(module_name, modules, config)
+                .iter()
+                .any(|(_, msg)| msg.raw_name == "COMMAND_LONG")
+    }
+
+    /// A `cmd` module with a struct per MAV_CMD entry holding its params,
+    /// converting to and from COMMAND_LONG and COMMAND_INT.  Commands of
+    /// includes with a `cmd` module are re-exported from there.
+    fn emit_commands(
+        &self,
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
+    ) -> TokenStream {
+        if !self.has_commands(module_name, modules, config) {
+            return quote!();
+        }
+        let module_ident = ident(module_name);
+        let messages = self.dialect_messages(module_name, modules, config);
+        let message_module = |raw_name: &str| {
+            messages
+                .iter()
+                .find(|(_, msg)| msg.raw_name == raw_name)
+                .map(|(dialect, _)| ident(dialect))
+        };
+        let long_module = message_module("COMMAND_LONG").unwrap();
+        let int_module = message_module("COMMAND_INT");
+
+        let mut inherited = vec![];
+        let mut reexports = vec![];
+        let mut frames = None;
+        for inc in self.all_includes(modules) {
+            let inc_name = config.module_name(&inc);
+            let inc_profile = modules
+                .get(&inc)
+                .unwrap_or_else(|| panic!("Module {} not loaded!", inc));
+            if frames.is_none() {
+                frames = inc_profile.enums.iter().find(|e| e.raw_name == "MAV_FRAME");
+            }
+            let defines_commands = inc_profile.enums.iter().any(|e| e.raw_name == "MAV_CMD");
+            if defines_commands && inc_profile.has_commands(&inc_name, modules, config) {
+                let include = ident(&inc_name);
+                reexports.push(quote!(pub use crate::mavlink::#include::cmd::*;));
+                for e in inc_profile.enums.iter().filter(|e| e.raw_name == "MAV_CMD") {
+                    inherited.extend(e.entries.iter().filter_map(|entry| entry.value));
+                }
+            }
+        }
+        let frames = self
+            .enums
+            .iter()
+            .find(|e| e.raw_name == "MAV_FRAME")
+            .or(frames);
+
+        let own = self
+            .enums
+            .iter()
+            .filter(|e| e.raw_name == "MAV_CMD")
+            // an alias has the struct of the first entry with its value
+            .flat_map(|e| {
+                e.entries
+                    .iter()
+                    .filter(move |entry| e.alias_of(entry).is_none())
+            })
+            .filter(|entry| !matches!(entry.value, Some(value) if inherited.contains(&value)))
+            .collect::<Vec<&MavEnumEntry>>();
+        let commands = own
+            .iter()
+            .map(|entry| entry.emit_command(&module_ident, &long_module, int_module.as_ref()))
+            .collect::<Vec<TokenStream>>();
+        let has_position = own
+            .iter()
+            .flat_map(|entry| &entry.params)
+            .flatten()
+            .any(|param| (param.index == 5 || param.index == 6) && param.field_name().is_some());
+
+        // COMMAND_INT has x/y as integers: degE7 in global frames, the
+        // params themselves in the mission frame, 1e4 per meter otherwise
+        let position_scale = match (&int_module, frames) {
+            (Some(_), Some(frames)) if has_position => {
+                let frame_values = |part: &str| {
+                    frames
+                        .entries
+                        .iter()
+                        .filter(|entry| entry.raw_name.contains(part))
+                        .filter_map(|entry| entry.value)
+                        .map(|value| Literal::i32_unsuffixed(value as i32))
+                        .collect::<Vec<Literal>>()
+                };
+                let global = frame_values("_GLOBAL");
+                let mission = frame_values("_MISSION");
+                quote! {
+                    /// Scale of the COMMAND_INT `x` and `y` fields in `frame`.
+                    fn position_scale(frame: i32) -> f64 {
+                        match frame {
+                            #(#global)|* => 1e7,
+                            #(#mission)|* => 1.0,
+                            _ => 1e4,
+                        }
+                    }
+                }
+            }
+            _ => quote!(),
+        };
+
+        quote! {
+            /// MAV_CMD entries as structs of their params, for building and
+            /// reading COMMAND_LONG and COMMAND_INT messages.
+            pub mod cmd {
+                #(#reexports)*
+
+                #position_scale
+
+                #(#commands)*
+            }
+        }
+    }
+
+    fn emit_mav_message(
+        &self,
+        enums: &[TokenStream],
+        structs: &[TokenStream],
+        includes: &[Ident],
+    ) -> TokenStream {
+        #[cfg(feature = "emit-description")]
+        let descriptions = self
+            .messages
+            .iter()
+            .map(|msg| msg.emit_description())
+            .collect::<Vec<TokenStream>>();
+
+        #[cfg(not(feature = "emit-description"))]
+        let descriptions = self.messages.iter().map(|_| quote!());
+
+        let deprecations = self.messages.iter().map(|msg| match &msg.deprecated {
+            Some(deprecated) => {
+                let note = deprecated.note();
+                quote!(#[deprecated(note = #note)])
+            }
+            None => quote!(),
+        });
+
+        let includes = includes.iter().map(|include| {
+            let include_rusty = ident(&rusty_name(&include.to_string()));
+            quote! {
+                #include_rusty(crate::mavlink::#include::MavMessage)
+            }
+        });
+
+        // The message goes next to the tag instead of into the same map, the
+        // variants of included dialects are tagged MavMessage enums themselves.
+        #[cfg(feature = "serde")]
+        let serde = quote! {
+            #[derive(serde::Serialize, serde::Deserialize)]
+            #[serde(tag = "type", content = "message")]
+        };
+
+        #[cfg(not(feature = "serde"))]
+        let serde = quote!();
+
+        quote! {
+            #serde
+            pub enum MavMessage {
+                #(#descriptions #deprecations #enums(#structs),)*
+                #(#includes,)*
+            }
+        }
+    }
+
+    /// Field-less enum naming every message reachable from this dialect,
+    /// including the messages of included dialects.
+    fn emit_mav_message_kind(
+        &self,
+        enums: &[TokenStream],
+        includes: &[Ident],
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
+    ) -> TokenStream {
+        let all_messages = self.all_messages(modules);
+        let kinds = all_messages
+            .iter()
+            .map(|msg| ident(&msg.name))
+            .collect::<Vec<Ident>>();
+        let kind_names = all_messages
+            .iter()
+            .map(|msg| msg.name.clone())
+            .collect::<Vec<String>>();
+        let kind_ids = all_messages
+            .iter()
+            .map(|msg| Literal::u32_unsuffixed(msg.id))
+            .collect::<Vec<Literal>>();
+
+        let from_includes = self.includes.iter().map(|inc| {
+            let include = ident(&config.module_name(inc));
+            let arms = modules
+                .get(inc)
+                .unwrap_or_else(|| panic!("Module {} not loaded!", inc))
+                .all_messages(modules)
+                .iter()
+                .map(|msg| {
+                    let kind = ident(&msg.name);
+                    quote!(crate::mavlink::#include::MavMessageKind::#kind => MavMessageKind::#kind,)
+                })
+                .collect::<Vec<TokenStream>>();
+            quote! {
+                impl From<crate::mavlink::#include::MavMessageKind> for MavMessageKind {
+                    fn from(kind: crate::mavlink::#include::MavMessageKind) -> Self {
+                        match kind {
+                            #(#arms)*
+                        }
+                    }
+                }
+            }
+        });
+
+        let include_variants = includes
+            .iter()
+            .map(|include| ident(&rusty_name(&include.to_string())));
+
+        #[cfg(feature = "serde")]
+        let serde = quote!(#[derive(serde::Serialize, serde::Deserialize)]);
+
+        #[cfg(not(feature = "serde"))]
+        let serde = quote!();
+
+        quote! {
+            #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+            #serde
+            pub enum MavMessageKind {
+                #(#kinds,)*
+            }
+
+            impl MavMessageKind {
+                pub fn id(self) -> u32 {
+                    match self {
+                        #(MavMessageKind::#kinds => #kind_ids,)*
+                    }
+                }
+
+                pub fn name(self) -> &'static str {
+                    match self {
+                        #(MavMessageKind::#kinds => #kind_names,)*
+                    }
+                }
+            }
+
+            impl std::convert::TryFrom<u32> for MavMessageKind {
+                type Error = ParserError;
+
+                fn try_from(id: u32) -> Result<Self, ParserError> {
+                    match id {
+                        #(#kind_ids => Ok(MavMessageKind::#kinds),)*
+                        _ => Err(ParserError::UnknownMessage { id }),
+                    }
+                }
+            }
+
+            #(#from_includes)*
+
+            impl MavMessage {
+                /// The kind of this message, without looking at the payload.
+                // the kind of an include without messages is uninhabited
+                #[allow(unreachable_code)]
+                pub fn kind(&self) -> MavMessageKind {
+                    match *self {
+                        #(MavMessage::#enums(..) => MavMessageKind::#enums,)*
+                        #(MavMessage::#include_variants(ref msg) => msg.kind().into(),)*
+                    }
+                }
+            }
+        }
+    }
+
+    /// `MavMessage::target()`, where a router has to send a message.
+    fn emit_mav_message_target(&self, includes: &[Ident]) -> TokenStream {
+        let arms = self.messages.iter().filter_map(|msg| {
+            let has_field = |name: &str| msg.fields.iter().any(|f| f.name == name);
+            if !has_field("target_system") {
+                return None;
+            }
+            let name = ident(&msg.name);
+            let component = if has_field("target_component") {
+                quote!(body.target_component as u8)
+            } else {
+                quote!(0)
+            };
+            Some(quote! {
+                MavMessage::#name(ref body) => Some((body.target_system as u8, #component)),
+            })
+        });
+        let includes = includes
+            .iter()
+            .map(|include| ident(&rusty_name(&include.to_string())));
+
+        quote! {
+            impl MavMessage {
+                /// target_system and target_component of the message, the
+                /// component is 0 (all) for messages with only a
+                /// target_system.  `None` for messages without a target,
+                /// which go to everyone.
+                #[allow(unreachable_patterns)]
+                pub fn target(&self) -> Option<(u8, u8)> {
+                    match *self {
+                        #(#arms)*
+                        #(MavMessage::#includes(ref msg) => msg.target(),)*
+                        _ => None,
+                    }
+                }
+            }
+        }
+    }
+
+    fn emit_dialect_spec(
+        &self,
+        module_name: &str,
+        structs: &[TokenStream],
+        ids: &[TokenStream],
+        includes: &[Ident],
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
+    ) -> TokenStream {
+        let all_structs = self
+            .dialect_messages(module_name, modules, config)
+            .into_iter()
+            .map(|(dialect, msg)| msg.emit_struct_name(&dialect));
+
+        let includes_branch = includes.iter().map(|include| {
+            quote! {
+                if let Some(info) = crate::mavlink::#include::Dialect.message_info(id) {
+                    return Some(info);
+                }
+            }
+        });
+
+        // the commands of the includes as well, a dialect that only
+        // includes common has no MAV_CMD of its own
+        let mut commands: Vec<&MavEnumEntry> = vec![];
+        let includes = self.all_includes(modules);
+        let profiles =
+            std::iter::once(self).chain(includes.iter().filter_map(|inc| modules.get(inc)));
+        for e in profiles.flat_map(|profile| &profile.enums) {
+            if e.raw_name != "MAV_CMD" {
+                continue;
+            }
+            for entry in &e.entries {
+                if e.alias_of(entry).is_none() && !commands.iter().any(|c| c.value == entry.value) {
+                    commands.push(entry);
+                }
+            }
+        }
+        commands.sort_by_key(|entry| entry.value);
+        let commands = commands.iter().map(|entry| entry.emit_command_info());
+        let version = self.version.clone().unwrap_or_default();
+        let dialect = Literal::u8_unsuffixed(self.dialect.unwrap_or(0));
+
+        quote! {
+            /// `<version>` of the definitions, "" if neither this dialect nor
+            /// its includes have one.
+            pub const MAVLINK_VERSION: &str = #version;
+
+            /// `<dialect>` number, 0 if neither this dialect nor its includes
+            /// have one.
+            pub const DIALECT_NUMBER: u8 = #dialect;
+
+            /// Name, version and dialect number of this dialect.
+            pub const DIALECT_INFO: crate::DialectInfo = crate::DialectInfo {
+                name: #module_name,
+                mavlink_version: MAVLINK_VERSION,
+                dialect_number: DIALECT_NUMBER,
+            };
+
+            /// Every MAV_CMD entry of this dialect and its includes.
+            pub static COMMANDS: &[crate::CommandInfo] = &[#(#commands),*];
+
+            /// Every message of this dialect and its includes.
+            pub static MESSAGES: &[crate::MessageInfo] = &[#(#all_structs::INFO),*];
+
+            /// This dialect as a `DialectSpec` object.
+            #[derive(Clone, Copy, Debug, Default)]
+            pub struct Dialect;
+
+            impl MavMessage {
+                /// Id, name, crc_extra and lengths of a message of this dialect
+                /// (or its includes).
+                pub fn descriptor(id: u32) -> Option<crate::MessageInfo> {
+                    crate::DialectSpec::message_info(&Dialect, id)
+                }
+            }
+
+            impl crate::DialectSpec for Dialect {
+                fn name(&self) -> &'static str {
+                    #module_name
+                }
+
+                fn info(&self) -> crate::DialectInfo {
+                    DIALECT_INFO
+                }
+
+                fn messages(&self) -> &'static [crate::MessageInfo] {
+                    MESSAGES
+                }
+
+                fn commands(&self) -> &'static [crate::CommandInfo] {
+                    COMMANDS
+                }
+
+                fn message_info(&self, id: u32) -> Option<crate::MessageInfo> {
+                    match id {
+                        #(#ids => Some(#structs::INFO),)*
+                        _ => {
+                            #(#includes_branch)*
+                            None
+                        }
+                    }
+                }
+
+                fn parse(
+                    &self,
+                    version: MavlinkVersion,
+                    id: u32,
+                    payload: &[u8],
+                ) -> Result<Box<dyn crate::DynMessage>, ParserError> {
+                    Ok(Box::new(MavMessage::parse(version, id, payload)?))
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_from_includes(&self, includes: &[Ident]) -> TokenStream {
+        let froms = includes.iter().map(|include| {
+            let include_rusty = ident(&rusty_name(&include.to_string()));
+            quote! {
+                impl From<crate::mavlink::#include::MavMessage> for MavMessage {
+                    fn from(message: crate::mavlink::#include::MavMessage) -> Self {
+                        MavMessage::#include_rusty(message)
+                    }
+                }
+            }
+        });
+
+        quote! {
+            #(#froms)*
+        }
+    }
+
+    fn emit_mav_message_parse(
+        &self,
+        enums: &[TokenStream],
+        structs: &[TokenStream],
+        ids: &[TokenStream],
+        includes: &[Ident],
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+    ) -> TokenStream {
+        let id_width = quote!(u32);
+
+        // messages of the includes go straight to the include defining them
+        let includes_arms = self.emit_include_message_arms(
+            module_name,
+            includes,
+            modules,
+            quote!(parse(version, id, payload)),
+        );
+
+        // without messages and includes (e.g. filtered out) the arguments are unused
+        quote! {
+            #[allow(unused_variables)]
+            fn parse(version: MavlinkVersion, id: #id_width, payload: &[u8]) -> Result<MavMessage, ParserError> {
+                match id {
+                    #(#ids => #structs::mavlink_deser(version, payload).map(MavMessage::#enums),)*
+                    #(#includes_arms)*
+                    _ => Err(ParserError::UnknownMessage { id }),
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_proto_parse(
+        &self,
+        enums: &[TokenStream],
+        structs: &[TokenStream],
+        ids: &[TokenStream],
+        includes: &[Ident],
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+    ) -> TokenStream {
+        let id_width = quote!(u32);
+
+        let includes_arms = self.emit_include_message_arms(
+            module_name,
+            includes,
+            modules,
+            quote!(proto_parse(id, payload)),
+        );
+
+        quote! {
+            #[allow(unused_variables)]
+            fn proto_parse(id: #id_width, payload: &[u8]) -> Result<MavMessage, ParserError> {
+                match id {
+                    #(#ids => #structs::decode(payload).map(MavMessage::#enums).map_err(|error| ParserError::ProstDecode { error }),)*
+                    #(#includes_arms)*
+                    _ => Err(ParserError::UnknownMessage { id }),
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_crc(&self, modules: &HashMap<String, MavProfile>) -> TokenStream {
+        let mut messages = self.all_messages(modules);
+        messages.sort_by_key(|msg| msg.id);
+        let ids = messages.iter().map(|msg| Literal::u32_unsuffixed(msg.id));
+        let crcs = messages
+            .iter()
+            .map(|msg| Literal::u8_unsuffixed(extra_crc(msg)));
+
+        quote! {
+            /// crc_extra of every message of this dialect and its includes,
+            /// sorted by id.
+            pub static EXTRA_CRCS: &[(u32, u8)] = &[#((#ids, #crcs)),*];
+
+            impl MavMessage {
+                /// crc_extra of a message of this dialect (or its includes),
+                /// `None` for an unknown id where `Message::extra_crc()`
+                /// returns 0.
+                pub fn lookup_extra_crc(id: u32) -> Option<u8> {
+                    EXTRA_CRCS
+                        .binary_search_by_key(&id, |&(id, _)| id)
+                        .ok()
+                        .map(|i| EXTRA_CRCS[i].1)
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_name(&self, enums: &[TokenStream], includes: &[Ident]) -> TokenStream {
+        let enum_names = enums
+            .iter()
+            .map(|enum_name| enum_name.to_string())
+            .collect::<Vec<String>>();
+
+        let includes = includes
+            .iter()
+            .map(|include| ident(&rusty_name(&include.to_string())));
+
+        quote! {
+            fn message_name(&self) -> &'static str {
+                match *self {
+                    #(MavMessage::#enums(..) => #enum_names,)*
+                    #(MavMessage::#includes(ref msg) => msg.message_name(),)*
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_id(
+        &self,
+        enums: &[TokenStream],
+        ids: &[TokenStream],
+        includes: &[Ident],
+    ) -> TokenStream {
+        let id_width = quote!(u32);
+        let includes = includes
+            .iter()
+            .map(|include| ident(&rusty_name(&include.to_string())));
+
+        quote! {
+            fn message_id(&self) -> #id_width {
+                match *self {
+                    #(MavMessage::#enums(..) => #ids,)*
+                    #(MavMessage::#includes(ref msg) => msg.message_id(),)*
+                }
+            }
+        }
+    }
+
+    /// The ids of the messages each direct include contributes, in include
+    /// order, the same message reached through several includes belongs to
+    /// the first.  Panics if an id is used by two different messages, the
+    /// generated code could only ever parse one of them.
+    fn include_message_ids(
+        &self,
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+    ) -> Vec<Vec<u32>> {
+        // id => message and the definition file it came from
+        let mut known: HashMap<u32, (MavMessage, &str)> = self
+            .messages
+            .iter()
+            .map(|msg| (msg.id, (msg.clone(), "this dialect")))
+            .collect();
+        self.includes
+            .iter()
+            .map(|inc| {
+                let inc_profile = modules
+                    .get(inc)
+                    .unwrap_or_else(|| panic!("Module {} not loaded!", inc));
+                let mut ids = vec![];
+                for msg in inc_profile.all_messages(modules) {
+                    match known.get(&msg.id) {
+                        Some((other, _))
+                            if other.raw_name == msg.raw_name
+                                && extra_crc(other) == extra_crc(&msg) => {}
+                        Some((other, source)) => panic!(
+                            "{}: message id {} is {} in {} and {} in {}",
+                            module_name, msg.id, other.raw_name, source, msg.raw_name, inc
+                        ),
+                        None => {
+                            ids.push(msg.id);
+                            known.insert(msg.id, (msg, inc));
+                        }
+                    }
+                }
+                ids
+            })
+            .collect()
+    }
+
+    /// Match arms handing the ids of each include to its `MavMessage`, one
+    /// jump instead of trying the includes one after the other.  `lookup`
+    /// gives the expression for the `MavMessage` of an include.
+    fn emit_include_id_arms<F: Fn(&Ident) -> TokenStream>(
+        &self,
+        module_name: &str,
+        includes: &[Ident],
+        modules: &HashMap<String, MavProfile>,
+        lookup: F,
+    ) -> Vec<TokenStream> {
+        includes
+            .iter()
+            .zip(self.include_message_ids(module_name, modules))
+            .filter(|(_, ids)| !ids.is_empty())
+            .map(|(include, ids)| {
+                let ids = ids.into_iter().map(Literal::u32_unsuffixed);
+                let lookup = lookup(include);
+                quote! {
+                    #(#ids)|* => #lookup,
+                }
+            })
+            .collect()
+    }
+
+    /// `emit_include_id_arms` for a lookup by id returning an include's
+    /// `MavMessage`, wrapped into ours.
+    fn emit_include_message_arms(
+        &self,
+        module_name: &str,
+        includes: &[Ident],
+        modules: &HashMap<String, MavProfile>,
+        lookup: TokenStream,
+    ) -> Vec<TokenStream> {
+        self.emit_include_id_arms(module_name, includes, modules, |include| {
+            let include_rusty = ident(&rusty_name(&include.to_string()));
+            quote! {
+                crate::mavlink::#include::MavMessage::#lookup.map(MavMessage::#include_rusty)
+            }
+        })
+    }
+
+    fn emit_mav_message_id_from_name(&self, modules: &HashMap<String, MavProfile>) -> TokenStream {
+        // the first message of a name wins, like falling through the includes
+        let mut messages = self.all_messages(modules);
+        messages.sort_by(|a, b| a.name.cmp(&b.name));
+        messages.dedup_by(|b, a| a.name == b.name);
+        let names = messages.iter().map(|msg| msg.name.as_str());
+        let ids = messages.iter().map(|msg| Literal::u32_unsuffixed(msg.id));
+
+        quote! {
+            fn message_id_from_name(name: &str) -> Result<u32, &'static str> {
+                // every message of this dialect and its includes, by name
+                static IDS: &[(&str, u32)] = &[#((#names, #ids)),*];
+                IDS.binary_search_by_key(&name, |&(name, _)| name)
+                    .map(|i| IDS[i].1)
+                    .map_err(|_| "Invalid message name.")
+            }
+        }
+    }
+
+    /// `default_message_from_name()`, taking the MAVLink names as well as the
+    /// names of the structs.
+    fn emit_mav_message_from_name(&self, modules: &HashMap<String, MavProfile>) -> TokenStream {
+        let mut messages = self.all_messages(modules);
+        messages.sort_by(|a, b| a.raw_name.cmp(&b.raw_name));
+        messages.dedup_by(|b, a| a.raw_name == b.raw_name);
+        let names = messages.iter().map(|msg| msg.raw_name.as_str());
+        let ids = messages.iter().map(|msg| Literal::u32_unsuffixed(msg.id));
+
+        quote! {
+            impl MavMessage {
+                /// The default message of a name, "HEARTBEAT" or "Heartbeat".
+                pub fn default_message_from_name(name: &str) -> Result<MavMessage, &'static str> {
+                    // every message of this dialect and its includes, by MAVLink name
+                    static MAV_IDS: &[(&str, u32)] = &[#((#names, #ids)),*];
+                    let id = match MAV_IDS.binary_search_by_key(&name, |&(name, _)| name) {
+                        Ok(i) => MAV_IDS[i].1,
+                        Err(_) => MavMessage::message_id_from_name(name)?,
+                    };
+                    MavMessage::default_message_from_id(id)
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_default_from_id(
+        &self,
+        enums: &[TokenStream],
+        ids: &[TokenStream],
+        includes: &[Ident],
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+    ) -> TokenStream {
+        let module_ident = ident(module_name);
+        let data_name = enums
+            .iter()
+            .map(|enum_name| quote!(crate::proto::#module_ident::#enum_name))
+            .collect::<Vec<TokenStream>>();
+        let includes_arms = self.emit_include_message_arms(
+            module_name,
+            includes,
+            modules,
+            quote!(default_message_from_id(id)),
+        );
+
+        quote! {
+            fn default_message_from_id(id: u32) -> Result<MavMessage, &'static str> {
+                match id {
+                    #(#ids => Ok(MavMessage::#enums(#data_name::default())),)*
+                    #(#includes_arms)*
+                    _ => Err("Invalid message id."),
+                }
+            }
+        }
+    }
+
+    /// Like `default_message_from_id`, but with the fields that have a value
+    /// for "unknown" set to it (the `unset()` of the structs).
+    fn emit_mav_message_unset_from_id(
+        &self,
+        enums: &[TokenStream],
+        ids: &[TokenStream],
+        includes: &[Ident],
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+    ) -> TokenStream {
+        let module_ident = ident(module_name);
+        let data_name = enums
+            .iter()
+            .map(|enum_name| quote!(crate::proto::#module_ident::#enum_name))
+            .collect::<Vec<TokenStream>>();
+        let includes_arms = self.emit_include_message_arms(
+            module_name,
+            includes,
+            modules,
+            quote!(unset_from_id(id)),
+        );
+
+        quote! {
+            impl MavMessage {
+                /// A message with every field that can be "unknown" (NaN,
+                /// UINT16_MAX, ...) set so, all others zero.
+                pub fn unset_from_id(id: u32) -> Result<MavMessage, &'static str> {
+                    match id {
+                        #(#ids => Ok(MavMessage::#enums(#data_name::unset())),)*
+                        #(#includes_arms)*
+                        _ => Err("Invalid message id."),
+                    }
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_serialize(&self, enums: &[TokenStream], includes: &[Ident]) -> TokenStream {
+        let includes = includes
+            .iter()
+            .map(|include| ident(&rusty_name(&include.to_string())));
+
+        quote! {
+            fn mavlink_ser(&self) -> Vec<u8> {
+                match *self {
+                    #(MavMessage::#enums(ref body) => body.mavlink_ser(),)*
+                    #(MavMessage::#includes(ref msg) => msg.mavlink_ser(),)*
+                }
+            }
+        }
+    }
+
+    fn emit_mav_message_serialize_into(
+        &self,
+        enums: &[TokenStream],
+        includes: &[Ident],
+    ) -> TokenStream {
+        let includes = includes
+            .iter()
+            .map(|include| ident(&rusty_name(&include.to_string())));
+
+        quote! {
+            /// Write the MAVLink payload to `buf` without allocating, see
+            /// the `mavlink_ser_into()` of the message structs.
+            pub fn mavlink_ser_into(&self, buf: &mut [u8]) -> Result<usize, crate::BufferError> {
+                match *self {
+                    #(MavMessage::#enums(ref body) => body.mavlink_ser_into(buf),)*
+                    #(MavMessage::#includes(ref msg) => msg.mavlink_ser_into(buf),)*
+                }
+            }
+        }
+    }
+
+    fn emit_proto_message_serialize(
+        &self,
+        enums: &[TokenStream],
+        includes: &[Ident],
+    ) -> TokenStream {
+        let includes = includes
+            .iter()
+            .map(|include| ident(&rusty_name(&include.to_string())));
+
+        quote! {
+            fn proto_encode(&self) -> Vec<u8> {
+                match *self {
+                    #(MavMessage::#enums(ref body) => body.encode_to_vec(),)*
+                    #(MavMessage::#includes(ref msg) => msg.proto_encode(),)*
+                }
+            }
+        }
+    }
+}
+
+impl MavEnumEntry {
+    /// The struct of a MAV_CMD entry.  Params 5 and 6 (latitude and
+    /// longitude in global frames) are f64 to keep the COMMAND_INT
+    /// precision, the others are f32 like the message fields.
+    fn emit_command(
+        &self,
+        module_ident: &Ident,
+        long_module: &Ident,
+        int_module: Option<&Ident>,
+    ) -> TokenStream {
+        let name = ident(&self.name);
+        let params = self.params.clone().unwrap_or_default();
+        let mut names: Vec<String> = vec![];
+        // (param index, field) of the params with a meaning
+        let mut fields: Vec<(usize, Ident)> = vec![];
+        let mut field_defs = vec![];
+        for param in &params {
+            let mut field_name = match param.field_name() {
+                Some(field_name) => field_name,
+                None => continue,
+            };
+            if names.contains(&field_name) {
+                field_name = format!("param{}", param.index);
+            }
+            names.push(field_name.clone());
+            let field = ident(&field_name);
+            let field_type = command_param_type(param.index);
+
+            #[cfg(feature = "emit-description")]
+            let description = {
+                let desc = format!(
+                    " {}",
+                    param
+                        .description
+                        .split_whitespace()
+                        .collect::<Vec<&str>>()
+                        .join(" ")
+                );
+                quote!(#[doc = #desc])
+            };
+
+            #[cfg(not(feature = "emit-description"))]
+            let description = quote!();
+
+            field_defs.push(quote! {
+                #description
+                pub #field: #field_type,
+            });
+            fields.push((param.index, field));
+        }
+
+        let field_of = |index: usize| fields.iter().find(|(i, _)| *i == index).map(|(_, f)| f);
+        let default_of = |index: usize| {
+            params
+                .iter()
+                .find(|param| param.index == index)
+                .and_then(|param| param.default)
+                .unwrap_or(0.0)
+        };
+        let long_params = (1..=7usize).map(|index| {
+            let param = format_ident!("param{}", index);
+            match field_of(index) {
+                Some(field) if index == 5 || index == 6 => quote!(#param: self.#field as f32),
+                Some(field) => quote!(#param: self.#field),
+                None => {
+                    let value = float_tokens(default_of(index));
+                    quote!(#param: #value)
+                }
+            }
+        });
+        let from_long = fields.iter().map(|(index, field)| {
+            let param = format_ident!("param{}", index);
+            quote!(#field: msg.#param.into())
+        });
+
+        let mut int_params = vec![];
+        for (index, int_field) in (1..=7usize).zip(
+            ["param1", "param2", "param3", "param4", "x", "y", "z"]
+                .iter()
+                .map(|name| format_ident!("{}", name)),
+        ) {
+            int_params.push(match (field_of(index), index) {
+                (Some(field), 5) | (Some(field), 6) => {
+                    quote!(#int_field: (self.#field * position_scale(frame as i32)).round() as i32)
+                }
+                (Some(field), _) => quote!(#int_field: self.#field),
+                // NaN is sent as INT32_MAX in the integer fields
+                (None, 5) | (None, 6) if default_of(index).is_nan() => {
+                    quote!(#int_field: i32::MAX)
+                }
+                (None, 5) | (None, 6) => quote!(#int_field: 0),
+                (None, _) => {
+                    let value = float_tokens(default_of(index));
+                    quote!(#int_field: #value)
+                }
+            });
+        }
+        let from_int = fields.iter().map(|(index, field)| match index {
+            5 => quote!(#field: f64::from(msg.x) / position_scale(msg.frame)),
+            6 => quote!(#field: f64::from(msg.y) / position_scale(msg.frame)),
+            7 => quote!(#field: msg.z),
+            index => {
+                let param = format_ident!("param{}", index);
+                quote!(#field: msg.#param)
+            }
+        });
+
+        let command_int = match int_module {
+            Some(int_module) => quote! {
+                impl #name {
+                    /// COMMAND_INT for `target_system` and `target_component`,
+                    /// with the position in `frame`.
+                    pub fn into_command_int(
+                        self,
+                        target_system: u8,
+                        target_component: u8,
+                        frame: crate::proto::#int_module::MavFrame,
+                    ) -> crate::proto::#int_module::CommandInt {
+                        crate::proto::#int_module::CommandInt {
+                            target_system: target_system.into(),
+                            target_component: target_component.into(),
+                            command: Self::COMMAND as i32,
+                            frame: frame as i32,
+                            current: 0,
+                            autocontinue: 0,
+                            #(#int_params,)*
+                        }
+                    }
+                }
+
+                impl std::convert::TryFrom<&crate::proto::#int_module::CommandInt> for #name {
+                    type Error = crate::CommandError;
+
+                    fn try_from(msg: &crate::proto::#int_module::CommandInt) -> Result<Self, crate::CommandError> {
+                        if msg.command != Self::COMMAND as i32 {
+                            return Err(crate::CommandError {
+                                expected: Self::COMMAND as i32 as u32,
+                                found: msg.command as u32,
+                            });
+                        }
+                        Ok(Self { #(#from_int),* })
+                    }
+                }
+            },
+            None => quote!(),
+        };
+
+        let deprecation = match &self.deprecated {
+            Some(deprecated) => {
+                let note = deprecated.note();
+                quote!(#[deprecated(note = #note)])
+            }
+            None => quote!(),
+        };
+
+        #[cfg(feature = "emit-description")]
+        let description = {
+            let desc = self
+                .description
+                .iter()
+                .map(|val| format!(" {}", val.trim()));
+            quote!(#(#[doc = #desc])*)
+        };
+
+        #[cfg(not(feature = "emit-description"))]
+        let description = quote!();
+
+        let command_doc = format!(" {}", self.raw_name);
+
+        quote! {
+            #description
+            #deprecation
+            #[derive(Clone, Copy, Debug, Default, PartialEq)]
+            pub struct #name {
+                #(#field_defs)*
+            }
+
+            impl #name {
+                #[doc = #command_doc]
+                pub const COMMAND: crate::proto::#module_ident::MavCmd =
+                    crate::proto::#module_ident::MavCmd::#name;
+
+                /// COMMAND_LONG for `target_system` and `target_component`.
+                pub fn into_command_long(
+                    self,
+                    target_system: u8,
+                    target_component: u8,
+                ) -> crate::proto::#long_module::CommandLong {
+                    crate::proto::#long_module::CommandLong {
+                        target_system: target_system.into(),
+                        target_component: target_component.into(),
+                        command: Self::COMMAND as i32,
+                        confirmation: 0,
+                        #(#long_params,)*
+                    }
+                }
+            }
+
+            impl std::convert::TryFrom<&crate::proto::#long_module::CommandLong> for #name {
+                type Error = crate::CommandError;
+
+                fn try_from(msg: &crate::proto::#long_module::CommandLong) -> Result<Self, crate::CommandError> {
+                    if msg.command != Self::COMMAND as i32 {
+                        return Err(crate::CommandError {
+                            expected: Self::COMMAND as i32 as u32,
+                            found: msg.command as u32,
+                        });
+                    }
+                    Ok(Self { #(#from_long),* })
+                }
+            }
+
+            #command_int
+        }
+    }
+}
+
+impl MavEnumEntry {
+    /// `CommandInfo` of a MAV_CMD entry.
+    fn emit_command_info(&self) -> TokenStream {
+        let id = Literal::u32_unsuffixed(self.value.unwrap_or(0));
+        let name = self.name.clone();
+        let params = self.params.iter().flatten().map(|param| param.emit_info());
+        quote! {
+            crate::CommandInfo {
+                id: #id,
+                name: #name,
+                params: &[#(#params),*],
+            }
+        }
+    }
+}
+
+impl MavCmdParam {
+    fn emit_info(&self) -> TokenStream {
+        let optional_str = |value: &Option<String>| match value {
+            Some(value) => quote!(Some(#value)),
+            None => quote!(None),
+        };
+        let optional_f32 = |value: Option<f32>| match value {
+            Some(value) => {
+                let value = float_tokens(value);
+                quote!(Some(#value))
+            }
+            None => quote!(None),
+        };
+        let index = Literal::u8_unsuffixed(self.index as u8);
+        let label = optional_str(&self.label);
+        let description = self
+            .description
+            .split_whitespace()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let units = optional_str(&self.units);
+        let enum_type = optional_str(&self.enumtype);
+        let min_value = optional_f32(self.min_value);
+        let max_value = optional_f32(self.max_value);
+        let increment = optional_f32(self.increment);
+        let reserved = self.reserved;
+        let default = optional_f32(self.default);
+        quote! {
+            crate::CommandParamInfo {
+                index: #index,
+                label: #label,
+                description: #description,
+                units: #units,
+                enum_type: #enum_type,
+                min_value: #min_value,
+                max_value: #max_value,
+                increment: #increment,
+                reserved: #reserved,
+                default: #default,
+            }
+        }
+    }
+
+    /// Field of the param in its command struct, `None` for reserved and
+    /// empty params.  Named after the label, or `param<N>` without one.
+    fn field_name(&self) -> Option<String> {
+        let description = self.description.trim();
+        if self.reserved
+            || ["Empty", "Reserved", "Unused"]
+                .iter()
+                .any(|unused| self.label.is_none() && description.starts_with(unused))
+        {
+            return None;
+        }
+        match &self.label {
+            Some(label) if label.starts_with(char::is_alphabetic) => Some(snake_name(
+                &label.replace(|c: char| !c.is_alphanumeric(), " "),
+            )),
+            _ => Some(format!("param{}", self.index)),
+        }
+    }
+}
+
+/// Field type of a param, see [`MavEnumEntry::emit_command`].
+fn command_param_type(index: usize) -> TokenStream {
+    if index == 5 || index == 6 {
+        quote!(f64)
+    } else {
+        quote!(f32)
+    }
+}
+
+fn float_tokens(value: f32) -> TokenStream {
+    if value.is_nan() {
+        quote!(f32::NAN)
+    } else {
+        let value = Literal::f32_unsuffixed(value);
+        quote!(#value)
+    }
+}
+
+impl MavMessage {
+    /// Return Token of "MESSAGE_NAME_DATA
+    /// for mavlink struct data
+    fn emit_struct_name(&self, module_name: &str) -> TokenStream {
+        let module = ident(module_name);
+        let name = ident(&self.name);
+        quote!(crate::proto::#module::#name)
+    }
+
+    fn emit_name_types(&self) -> (Vec<TokenStream>, usize) {
+        let mut encoded_payload_len: usize = 0;
+        let field_toks = self
+            .fields
+            .iter()
+            .map(|field| {
+                let nametype = field.emit_name_type();
+                encoded_payload_len += field.mavtype.len();
+
+                #[cfg(feature = "emit-description")]
+                let description = field.emit_description();
+
+                #[cfg(not(feature = "emit-description"))]
+                let description = quote!();
+
+                quote! {
+                    #description
+                    #nametype
+                }
+            })
+            .collect::<Vec<TokenStream>>();
+        (field_toks, encoded_payload_len)
+    }
+
+    /// Generate description for the given message
+    #[cfg(feature = "emit-description")]
+    fn emit_description(&self) -> TokenStream {
+        let desc = self
+            .description
+            .iter()
+            .map(|val| format!(" {}", val.trim()));
+        let id = format!(" MavLink id: {}", self.id);
+        quote! {
+            #(#[doc = #desc] #[doc = ""])*
+            #[doc = #id]
+        }
+    }
+
+    fn emit_serialize_vars(&self) -> TokenStream {
+        let ser_vars = self
+            .fields
+            .iter()
+            .map(|f| f.rust_writer())
+            .collect::<Vec<TokenStream>>();
+        quote! {
+            #(#ser_vars)*
+        }
+    }
+
+    fn emit_deserialize_vars(&self) -> TokenStream {
+        let deser_vars = self
+            .fields
+            .iter()
+            .map(|f| f.rust_reader())
+            .collect::<Vec<TokenStream>>();
+
+        if deser_vars.is_empty() {
+            // struct has no fields
+            quote! {
+                Ok(Self::default())
+            }
+        } else {
+            // Should look at getting rid of the #[allow... below but it is non-trivial.
+            quote! {
+                let avail_len = _input.len();
+
+                // read straight from the slice, no copy
+                let mut buf = _input;
+
+                // handle payload length truncuation due to empty fields
+                let mut payload_buf = [0; Self::ENCODED_LEN];
+                if avail_len < Self::ENCODED_LEN {
+                    //copy available bytes into a stack buffer filled with zeros
+                    payload_buf[0..avail_len].copy_from_slice(_input);
+                    buf = &payload_buf[..];
+                }
+
+                #[allow(clippy::field_reassign_with_default)]
+                {
+                    let mut _struct = Self::default();
+                    #(#deser_vars)*
+                    Ok(_struct)
+                }
+            }
+        }
+    }
+
+    /// Raw byte accessors for char array fields, the `String` field itself
+    /// can not represent arbitrary bytes (serial numbers, UIDs, etc), their
+    /// `<FIELD>_MAX_LEN` and a `validate()` of the string lengths.
+    fn emit_string_accessors(&self) -> Vec<TokenStream> {
+        let strings = self
+            .fields
+            .iter()
+            .filter_map(|f| match &f.mavtype {
+                MavType::Array(t, size) if **t == MavType::Char => Some((f, *size)),
+                _ => None,
+            })
+            .collect::<Vec<(&MavField, usize)>>();
+        let checks = strings.iter().map(|(f, _)| {
+            let name = ident(&f.name);
+            let max_len =
+                format_ident!("{}_MAX_LEN", f.name.trim_start_matches("r#").to_uppercase());
+            let raw_name = f.raw_name.clone();
+            quote! {
+                let len = crate::mav_string::to_bytes(&self.#name).len();
+                if len > Self::#max_len {
+                    return Err(crate::LengthError {
+                        field: #raw_name,
+                        len,
+                        max_len: Self::#max_len,
+                    });
+                }
+            }
+        });
+        let validate = if strings.is_empty() {
+            quote!()
+        } else {
+            quote! {
+                /// Check that the strings fit their char arrays, `mavlink_ser`
+                /// cuts them off.
+                pub fn validate(&self) -> Result<(), crate::LengthError> {
+                    #(#checks)*
+                    Ok(())
+                }
+            }
+        };
+        strings
+            .iter()
+            .map(|(f, size)| {
+                let name = ident(&f.name);
+                let base = f.name.trim_start_matches("r#");
+                let getter = format_ident!("{}_bytes", base);
+                let setter = format_ident!("set_{}_bytes", base);
+                let max_len = format_ident!("{}_MAX_LEN", base.to_uppercase());
+                let doc = format!(" Size of the `{}` char array in bytes.", base);
+                quote! {
+                    #[doc = #doc]
+                    pub const #max_len: usize = #size;
+
+                    pub fn #getter(&self) -> Vec<u8> {
+                        crate::mav_string::to_bytes(&self.#name)
+                    }
+
+                    pub fn #setter(&mut self, bytes: &[u8]) {
+                        self.#name = crate::mav_string::from_bytes(bytes);
+                    }
+                }
+            })
+            .chain(std::iter::once(validate))
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// Range checked setters for integer fields that are narrower on the wire
+    /// than in the proto struct (mavlink_ser would silently truncate).
+    /// Enum fields are left to the typed setters prost generates.
+    fn emit_range_setters(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .filter(|f| f.enumtype.is_none() && !f.bytes)
+            .filter_map(|f| {
+                let (min, max) = f.mavtype.int_range()?;
+                let name = ident(&f.name);
+                let base = f.name.trim_start_matches("r#");
+                let setter = format_ident!("set_{}_checked", base);
+                let raw_name = f.raw_name.clone();
+                let check = quote! {
+                    if !(#min..=#max).contains(&(value as i64)) {
+                        return Err(crate::RangeError {
+                            field: #raw_name,
+                            value: value as i64,
+                            min: #min,
+                            max: #max,
+                        });
+                    }
+                };
+                Some(match &f.mavtype {
+                    MavType::Array(t, _) => {
+                        let value_type = ident(&t.proto_rust_type());
+                        quote! {
+                            pub fn #setter(&mut self, values: Vec<#value_type>) -> Result<(), crate::RangeError> {
+                                for &value in &values {
+                                    #check
+                                }
+                                self.#name = values;
+                                Ok(())
+                            }
+                        }
+                    }
+                    t => {
+                        let value_type = ident(&t.proto_rust_type());
+                        quote! {
+                            pub fn #setter(&mut self, value: #value_type) -> Result<(), crate::RangeError> {
+                                #check
+                                self.#name = value;
+                                Ok(())
+                            }
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// `<field>_<unit>()` accessors converting scaled integer fields (degE7,
+    /// cm/s, mV, ...) to f64 in SI units, the unit suffix of the field name
+    /// is dropped (`time_boot_ms` becomes `time_boot_s()`).
+    #[cfg(feature = "emit-units")]
+    fn emit_unit_accessors(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .filter(|f| f.enumtype.is_none())
+            .filter(|f| {
+                !matches!(
+                    f.mavtype,
+                    MavType::Array(..) | MavType::Float | MavType::Double | MavType::Char
+                )
+            })
+            .filter_map(|f| {
+                let units = f.units.as_ref()?;
+                let (suffix, si_unit, scale) = si_scale(units)?;
+                let name = ident(&f.name);
+                let base = f.name.trim_start_matches("r#");
+                let unit_suffix = match units.as_str() {
+                    "us" => "_usec".to_string(),
+                    units => format!("_{}", units.to_lowercase()),
+                };
+                let base = base.strip_suffix(&unit_suffix).unwrap_or(base);
+                let accessor = format_ident!("{}_{}", base, suffix);
+                let doc = format!(
+                    " `{}` in {} (stored in {}).",
+                    f.name.trim_start_matches("r#"),
+                    si_unit,
+                    units
+                );
+                let scale = Literal::f64_unsuffixed(scale);
+                Some(quote! {
+                    #[doc = #doc]
+                    pub fn #accessor(&self) -> f64 {
+                        self.#name as f64 * #scale
+                    }
+                })
+            })
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// `<FIELD>_INVALID` constants with the sentinel of fields that have one.
+    fn emit_invalid_consts(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .filter_map(|f| {
+                let sentinel = f.emit_invalid_value()?;
+                let name = f.invalid_const();
+                let proto_type = ident(&f.proto_scalar_type());
+                let doc = format!(
+                    " Value of `{}` when unknown or unused ({}).",
+                    f.name.trim_start_matches("r#"),
+                    f.invalid_name().unwrap()
+                );
+                Some(quote! {
+                    #[doc = #doc]
+                    pub const #name: #proto_type = #sentinel;
+                })
+            })
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// `<field>_is_valid()` for fields that have a sentinel for unknown
+    /// values, arrays count as valid when their first element is set
+    /// (`[<sentinel>:]` in the XML), or any element for `[<sentinel>]`.
+    fn emit_validity_checks(&self) -> Vec<TokenStream> {
+        self.fields
+            .iter()
+            .filter_map(|f| {
+                f.emit_invalid_value()?;
+                let name = ident(&f.name);
+                let sentinel = f.invalid_const();
+                let checker = format_ident!("{}_is_valid", f.name.trim_start_matches("r#"));
+                let doc = format!(
+                    " False if `{}` is {}, its value for unknown or unused.",
+                    f.name.trim_start_matches("r#"),
+                    f.invalid_name().unwrap()
+                );
+                let is_set = match &f.invalid {
+                    Some(InvalidValue::NaN) => quote!(|value| !value.is_nan()),
+                    _ => quote!(|value| *value != Self::#sentinel),
+                };
+                let check = match &f.mavtype {
+                    MavType::Array(..) if f.invalid_all_elements => {
+                        quote!(self.#name.iter().any(#is_set))
+                    }
+                    MavType::Array(..) => quote!(self.#name.first().map_or(false, #is_set)),
+                    _ if f.invalid == Some(InvalidValue::NaN) => quote!(!self.#name.is_nan()),
+                    _ => quote!(self.#name != Self::#sentinel),
+                };
+                Some(quote! {
+                    #[doc = #doc]
+                    pub fn #checker(&self) -> bool {
+                        #check
+                    }
+                })
+            })
+            .collect::<Vec<TokenStream>>()
+    }
+
+    /// Constructor filling the fields that have a sentinel with it, prost
+    /// already derives `Default` (all zero).
+    fn emit_unset(&self) -> TokenStream {
+        let values = self
+            .fields
+            .iter()
+            .filter_map(|f| {
+                f.emit_invalid_value()?;
+                let name = ident(&f.name);
+                let sentinel = f.invalid_const();
+                let sentinel = quote!(Self::#sentinel);
+                Some(match &f.mavtype {
+                    MavType::Array(_, size) => {
+                        let size = Literal::usize_unsuffixed(*size);
+                        quote!(#name: vec![#sentinel; #size])
+                    }
+                    _ => quote!(#name: #sentinel),
+                })
+            })
+            .collect::<Vec<TokenStream>>();
+        let unset = if values.is_empty() {
+            quote!(Default::default())
+        } else {
+            quote! {
+                Self {
+                    #(#values,)*
+                    ..Default::default()
+                }
+            }
+        };
+        quote! {
+            /// Fields with a value for "unknown" (NaN, UINT16_MAX, ...) set
+            /// to it, all others zero.
+            pub fn unset() -> Self {
+                #unset
+            }
+        }
+    }
+
+    /// Borrowed view reading fields straight out of a payload slice, for
+    /// consumers that only need a few fields of a high rate message.
+    fn emit_view(&self, module_name: &str) -> TokenStream {
+        let msg_name = self.emit_struct_name(module_name);
+        let view_name = format_ident!("{}View", self.name);
+        let mut offset: usize = 0;
+        let accessors = self
+            .fields
+            .iter()
+            .map(|field| {
+                let name = ident(&field.name);
+                let accessor = field.mavtype.rust_view_reader(name, offset);
+                offset += field.mavtype.len();
+
+                #[cfg(feature = "emit-description")]
+                let description = field.emit_description();
+
+                #[cfg(not(feature = "emit-description"))]
+                let description = quote!();
+
+                quote! {
+                    #description
+                    #accessor
+                }
+            })
+            .collect::<Vec<TokenStream>>();
+
+        quote! {
+            #[derive(Clone, Copy, Debug)]
+            pub struct #view_name<'a> {
+                payload: &'a [u8],
+            }
+
+            impl<'a> #view_name<'a> {
+                pub fn new(payload: &'a [u8]) -> Self {
+                    Self { payload }
+                }
+
+                pub fn as_slice(&self) -> &'a [u8] {
+                    self.payload
+                }
+
+                /// Decode the whole message.
+                pub fn to_message(&self, version: MavlinkVersion) -> Result<#msg_name, ParserError> {
+                    #msg_name::mavlink_deser(version, self.payload)
+                }
+
+                #(#accessors)*
+            }
+        }
+    }
+
+    /// Static description of the message and its fields, in wire order.
+    fn emit_info(&self) -> TokenStream {
+        let id = Literal::u32_unsuffixed(self.id);
+        let name = self.name.clone();
+        let crc = Literal::u8_unsuffixed(extra_crc(self));
+        let has_extensions = self.has_extensions;
+        let fields = self.fields.iter().map(|f| {
+            let name = f.name.trim_start_matches("r#").to_string();
+            let mav_type = f.mavtype.mav_type();
+            let enum_type = match &f.raw_enumtype {
+                Some(enum_type) => quote!(Some(#enum_type)),
+                None => quote!(None),
+            };
+            let units = match &f.units {
+                Some(units) => quote!(Some(#units)),
+                None => quote!(None),
+            };
+            let is_extension = f.is_extension;
+            quote! {
+                crate::FieldInfo {
+                    name: #name,
+                    mav_type: #mav_type,
+                    enum_type: #enum_type,
+                    units: #units,
+                    is_extension: #is_extension,
+                }
+            }
+        });
+        quote! {
+            pub const INFO: crate::MessageInfo = crate::MessageInfo {
+                id: #id,
+                name: #name,
+                extra_crc: #crc,
+                encoded_len: Self::ENCODED_LEN,
+                min_encoded_len: Self::MIN_ENCODED_LEN,
+                has_extensions: #has_extensions,
+                fields: &[#(#fields),*],
+            };
+        }
+    }
+
+    /// Compile time checks of the payload lengths, the size computed from
+    /// the XML has to agree with the Rust wire types and fit in a frame.
+    fn emit_len_assertions(
+        &self,
+        module_name: &str,
+        msg_name: &TokenStream,
+        config: &BuildConfig,
+    ) -> TokenStream {
+        let mut wire_sizes = self.fields.iter().map(|f| f.mavtype.wire_size());
+        let wire_len = match wire_sizes.next() {
+            Some(first) => quote!(#first #(+ #wire_sizes)*),
+            None => quote!(0),
+        };
+        // oversized messages can only be used through the proto types
+        let frame_check = if self.fields.iter().map(|f| f.mavtype.len()).sum::<usize>() > 255 {
+            if !config.oversized_dialects.iter().any(|d| d == module_name) {
+                println!(
+                    "cargo:warning={} payload does not fit in a MAVLink frame",
+                    self.raw_name
+                );
+            }
+            quote!()
+        } else {
+            quote!(assert!(#msg_name::ENCODED_LEN <= 255);)
+        };
+        quote! {
+            const _: () = {
+                assert!(#msg_name::ENCODED_LEN == #wire_len);
+                assert!(#msg_name::MIN_ENCODED_LEN <= #msg_name::ENCODED_LEN);
+                #frame_check
+            };
+        }
+    }
+
+    fn emit_rust(&self, module_name: &str, config: &BuildConfig) -> TokenStream {
+        let msg_name = self.emit_struct_name(module_name);
+        let (_name_types, msg_encoded_len) = self.emit_name_types();
+        // MAVLink 1 payload, extension fields are always at the end
+        let msg_min_encoded_len: usize = self
+            .fields
+            .iter()
+            .filter(|f| !f.is_extension)
+            .map(|f| f.mavtype.len())
+            .sum();
+        let len_assertions = self.emit_len_assertions(module_name, &msg_name, config);
+
+        let deser_vars = self.emit_deserialize_vars();
+        let serialize_vars = self.emit_serialize_vars();
+        let string_accessors = self.emit_string_accessors();
+        let view = self.emit_view(module_name);
+        let range_setters = self.emit_range_setters();
+        let invalid_consts = self.emit_invalid_consts();
+        let validity_checks = self.emit_validity_checks();
+
+        #[cfg(feature = "emit-units")]
+        let unit_accessors = self.emit_unit_accessors();
+
+        #[cfg(not(feature = "emit-units"))]
+        let unit_accessors: Vec<TokenStream> = vec![];
+        let unset = self.emit_unset();
+        let info = self.emit_info();
+
+        quote! {
+            impl #msg_name {
+                /// Payload length with all fields, extensions included.
+                pub const ENCODED_LEN: usize = #msg_encoded_len;
+                /// Payload length without extension fields (the MAVLink 1 length).
+                pub const MIN_ENCODED_LEN: usize = #msg_min_encoded_len;
+
+                #info
+
+                #(#invalid_consts)*
+
+                pub fn mavlink_deser(_version: MavlinkVersion, _input: &[u8]) -> Result<Self, ParserError> {
+                    #deser_vars
+                }
+
+                pub fn mavlink_ser(&self) -> Vec<u8> {
+                    let mut _tmp = Vec::with_capacity(Self::ENCODED_LEN);
+                    #serialize_vars
+                    _tmp
+                }
+
+                /// Write the payload (`ENCODED_LEN` bytes, trailing zeros are
+                /// not cut off) to the start of `buf`, without allocating.
+                /// Returns the number of bytes written.
+                pub fn mavlink_ser_into(&self, buf: &mut [u8]) -> Result<usize, crate::BufferError> {
+                    if buf.len() < Self::ENCODED_LEN {
+                        return Err(crate::BufferError {
+                            len: buf.len(),
+                            needed: Self::ENCODED_LEN,
+                        });
+                    }
+                    #[allow(unused_mut)]
+                    let mut _tmp = &mut buf[..Self::ENCODED_LEN];
+                    #serialize_vars
+                    Ok(Self::ENCODED_LEN)
+                }
+
+                #(#string_accessors)*
+
+                #(#range_setters)*
+
+                #unset
+
+                #(#validity_checks)*
+
+                #(#unit_accessors)*
+            }
+
+            #len_assertions
+
+            #view
+        }
+    }
+}
+
+impl MavField {
+    /// Emit rust name of a given field
+    fn emit_name(&self) -> TokenStream {
+        let name = ident(&self.name);
+        quote!(#name)
+    }
+
+    /// Emit rust type of the field
+    fn emit_type(&self) -> TokenStream {
+        match self.mavtype {
+            MavType::Array(_, _) => self.mavtype.rust_type_tokens(),
+            _ => match self.enumtype {
+                Some(ref enumname) => {
+                    let enumname = ident(enumname);
+                    quote!(#enumname)
+                }
+                _ => self.mavtype.rust_type_tokens(),
+            },
+        }
+    }
+
+    /// Generate description for the given field
+    #[cfg(feature = "emit-description")]
+    fn emit_description(&self) -> TokenStream {
+        let desc = self
+            .description
+            .iter()
+            .map(|val| format!(" {}", val.trim()));
+        let units = self.units.iter().map(|units| format!(" Units: {}", units));
+        quote!(#(#[doc = #desc])* #(#[doc = #units])*)
+    }
+
+    /// Combine rust name and type of a given field
+    fn emit_name_type(&self) -> TokenStream {
+        let name = self.emit_name();
+        let fieldtype = self.emit_type();
+        quote!(pub #name: #fieldtype,)
+    }
+
+    /// Name of the sentinel for unknown values, as in the XML.
+    fn invalid_name(&self) -> Option<String> {
+        let scalar = match &self.mavtype {
+            MavType::Array(t, _) => t.as_ref(),
+            t => t,
+        };
+        match self.invalid.as_ref()? {
+            InvalidValue::Max => scalar.max_name().map(String::from),
+            InvalidValue::NaN => Some("NaN".into()),
+            InvalidValue::Int(value) => Some(value.to_string()),
+            InvalidValue::Float(value) => Some(value.to_string()),
+        }
+    }
+
+    /// Rust type of the field (of its elements for arrays) in the proto struct.
+    fn proto_scalar_type(&self) -> String {
+        match &self.mavtype {
+            _ if self.bytes => "u8".into(),
+            MavType::Array(t, _) => t.proto_rust_type(),
+            t => t.proto_rust_type(),
+        }
+    }
+
+    /// `<FIELD>_INVALID`, the constant holding the sentinel.
+    fn invalid_const(&self) -> Ident {
+        format_ident!(
+            "{}_INVALID",
+            self.name.trim_start_matches("r#").to_uppercase()
+        )
+    }
+
+    /// The sentinel for unknown values as a (scalar) value of the proto
+    /// struct type.  Enum fields are left alone, prost stores them as i32.
+    fn emit_invalid_value(&self) -> Option<TokenStream> {
+        if self.enumtype.is_some() {
+            return None;
+        }
+        let scalar = match &self.mavtype {
+            MavType::Array(t, _) => t.as_ref(),
+            t => t,
+        };
+        let wire_type = ident(&scalar.rust_type());
+        let proto_type = ident(&self.proto_scalar_type());
+        match self.invalid.as_ref()? {
+            InvalidValue::NaN => Some(quote!(#wire_type::NAN)),
+            InvalidValue::Max if wire_type == proto_type => Some(quote!(#wire_type::MAX)),
+            InvalidValue::Max => Some(quote!(#wire_type::MAX as #proto_type)),
+            InvalidValue::Int(value) => {
+                let value = Literal::i64_unsuffixed(*value);
+                Some(quote!(#value))
+            }
+            InvalidValue::Float(value) => {
+                let value = Literal::f64_unsuffixed(*value);
+                Some(quote!(#value))
+            }
+        }
+    }
+
+    /// Emit writer
+    fn rust_writer(&self) -> TokenStream {
+        let name = ident(&self.name);
+        self.mavtype.rust_writer(quote!(self.#name), quote!(_tmp))
+    }
+
+    /// Emit reader
+    fn rust_reader(&self) -> TokenStream {
+        let name = ident(&self.name);
+        let name = quote!(_struct.#name);
+        let buf = quote!(buf);
+        if let Some(enum_name) = &self.enumtype {
+            if let MavType::Array(_t, _size) = &self.mavtype {
+                return self.mavtype.rust_reader(name, buf, false);
+            }
+            // handle enum by FromPrimitive
+            let tmp = self.mavtype.rust_reader(quote!(let tmp), buf, false);
+            let val = format_ident!("from_{}", self.mavtype.rust_type());
+            quote!(
+                #tmp
+                #name = FromPrimitive::#val(tmp)
+                    .ok_or(ParserError::InvalidEnum { enum_type: #enum_name.to_string(), value: tmp as u32 })?;
+            )
+        } else {
+            // bytes are read as they are, other integers widened to 32 bit
+            self.mavtype.rust_reader(name, buf, !self.bytes)
+        }
+    }
+}
+
+impl MavType {
+    /// Rust type of the MAVLink struct field
+    pub fn rust_type_tokens(&self) -> TokenStream {
+        use self::MavType::*;
+        match self {
+            Array(t, size) => {
+                // const generics, arrays of any length derive like scalars
+                let t = t.rust_type_tokens();
+                let size = Literal::usize_unsuffixed(*size);
+                quote!([#t; #size])
+            }
+            t => {
+                let t = ident(&t.rust_type());
+                quote!(#t)
+            }
+        }
+    }
+
+    /// Encoded size of the type in terms of the Rust wire types, used to
+    /// cross check `len` at compile time.
+    pub fn wire_size(&self) -> TokenStream {
+        use self::MavType::*;
+        match self {
+            Char => quote!(core::mem::size_of::<u8>()),
+            Array(t, size) => {
+                let t = t.wire_size();
+                let size = Literal::usize_unsuffixed(*size);
+                quote!(#t * #size)
+            }
+            t => {
+                let t = t.rust_type_tokens();
+                quote!(core::mem::size_of::<#t>())
+            }
+        }
+    }
+
+    /// Emit reader of a given type
+    pub fn rust_reader(&self, val: TokenStream, buf: TokenStream, with_cast: bool) -> TokenStream {
+        use self::MavType::*;
+        match self.clone() {
+            Char if with_cast => quote! {#val = #buf.get_u8() as u32;},
+            Char => quote! {#val = #buf.get_u8();},
+            UInt8 if with_cast => quote! {#val = #buf.get_u8() as u32;},
+            UInt8 => quote! {#val = #buf.get_u8();},
+            UInt16 if with_cast => quote! {#val = #buf.get_u16_le() as u32;},
+            UInt16 => quote! {#val = #buf.get_u16_le();},
+            UInt32 => quote! {#val = #buf.get_u32_le();},
+            UInt64 => quote! {#val = #buf.get_u64_le();},
+            UInt8MavlinkVersion if with_cast => quote! {#val = #buf.get_u8() as u32;},
+            UInt8MavlinkVersion => quote! {#val = #buf.get_u8();},
+            Int8 if with_cast => quote! {#val = #buf.get_i8() as i32;},
+            Int8 => quote! {#val = #buf.get_i8();},
+            Int16 if with_cast => quote! {#val = #buf.get_i16_le() as i32;},
+            Int16 => quote! {#val = #buf.get_i16_le();},
+            Int32 => quote! {#val = #buf.get_i32_le();},
+            Int64 => quote! {#val = #buf.get_i64_le();},
+            Float => quote! {#val = #buf.get_f32_le();},
+            Double => quote! {#val = #buf.get_f64_le();},
+            Array(t, size) => {
+                if let Char = *t {
+                    quote! {
+                        let mut s = Vec::with_capacity(#size);
+                        for _ in 0..#size {
+                            s.push(#buf.get_u8());
+                        }
+                        #val = crate::mav_string::from_bytes(&s);
+                    }
+                } else {
+                    let r = t.rust_reader(quote!(let val), buf, with_cast);
+                    // the proto structs keep prost's Vec, allocate it once
+                    quote! {
+                        #val.reserve_exact(#size);
+                        for _ in 0..#size {
+                            #r
+                            #[allow(clippy::useless_conversion)]
+                            #val.push(val.into());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Emit a view accessor reading a field of this type at `offset`
+    pub fn rust_view_reader(&self, name: Ident, offset: usize) -> TokenStream {
+        use self::MavType::*;
+        match self.clone() {
+            Array(t, size) => match *t {
+                UInt8 | Char => quote! {
+                    pub fn #name(&self) -> &'a [u8] {
+                        crate::mav_view::slice(self.payload, #offset, #size)
+                    }
+                },
+                _ => {
+                    let elem_type = t.rust_type_tokens();
+                    let elem_len = t.len();
+                    quote! {
+                        pub fn #name(&self) -> [#elem_type; #size] {
+                            let mut out = [#elem_type::default(); #size];
+                            for (i, val) in out.iter_mut().enumerate() {
+                                *val = #elem_type::from_le_bytes(crate::mav_view::bytes(
+                                    self.payload,
+                                    #offset + i * #elem_len,
+                                ));
+                            }
+                            out
+                        }
+                    }
+                }
+            },
+            _ => {
+                // chars are plain bytes on the wire
+                let val_type = match self {
+                    Char => quote!(u8),
+                    _ => self.rust_type_tokens(),
+                };
+                quote! {
+                    pub fn #name(&self) -> #val_type {
+                        #val_type::from_le_bytes(crate::mav_view::bytes(self.payload, #offset))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Emit writer of a given type
+    pub fn rust_writer(&self, val: TokenStream, buf: TokenStream) -> TokenStream {
+        use self::MavType::*;
+        match self.clone() {
+            UInt8MavlinkVersion => quote! {#buf.put_u8(#val as u8);},
+            UInt8 => quote! {#buf.put_u8(#val as u8);},
+            Char => quote! {#buf.put_u8(#val as u8);}, //XXX already u8
+            UInt16 => quote! {#buf.put_u16_le(#val as u16);},
+            UInt32 => quote! {#buf.put_u32_le(#val as u32);},
+            Int8 => quote! {#buf.put_i8(#val as i8);},
+            Int16 => quote! {#buf.put_i16_le(#val as i16);},
+            Int32 => quote! {#buf.put_i32_le(#val as i32);},
+            Float => quote! {#buf.put_f32_le(#val as f32);},
+            UInt64 => quote! {#buf.put_u64_le(#val as u64);},
+            Int64 => quote! {#buf.put_i64_le(#val as i64);},
+            Double => quote! {#buf.put_f64_le(#val as f64);},
+            Array(t, size) => {
+                if let Char = *t {
+                    // always the full array, zero padded or cut off
+                    quote! {
+                        {
+                            let bytes = crate::mav_string::to_bytes(&#val);
+                            for i in 0..#size {
+                                #buf.put_u8(bytes.get(i).copied().unwrap_or(0));
+                            }
+                        }
+                    }
+                } else {
+                    // always the full array as well, the proto structs have
+                    // a Vec that can be of any length
+                    let w = t.rust_writer(quote!(val), buf);
+                    quote! {
+                        for i in 0..#size {
+                            let val = #val.get(i).copied().unwrap_or_default();
+                            #w
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
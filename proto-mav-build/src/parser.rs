@@ -0,0 +1,1381 @@
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::default::Default;
+use std::ffi::{OsStr, OsString};
+use std::fs::{read_dir, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::u32;
+
+use heck::{CamelCase, SnakeCase};
+use xml::reader::{EventReader, XmlEvent};
+
+use crate::config::BuildConfig;
+use crate::util::write_generated;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MavProfile {
+    pub includes: Vec<String>,
+    pub messages: Vec<MavMessage>,
+    pub enums: Vec<MavEnum>,
+    /// `<version>`, taken from the includes when the file has none.
+    pub version: Option<String>,
+    /// `<dialect>` number, taken from the includes when the file has none.
+    pub dialect: Option<u8>,
+}
+
+impl MavProfile {
+    /// Go over all fields in the messages, and if you encounter an enum,
+    /// update this enum with information about whether it is a bitmask, and what
+    /// is the desired width of such.
+    fn update_enums(mut self) -> Self {
+        for msg in &self.messages {
+            for field in &msg.fields {
+                if let Some(ref enum_name) = field.enumtype {
+                    // it is an enum
+                    if let Some(ref dsp) = field.display {
+                        // it is a bitmask
+                        if dsp == "bitmask" {
+                            // find the corresponding enum
+                            for mut enm in &mut self.enums {
+                                if enm.name == *enum_name {
+                                    // this is the right enum
+                                    if enm.bitfield.is_none() {
+                                        enm.bitfield = Some("u32".into());
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Store the uint8_t arrays (that are not enums) as proto `bytes`.
+    pub fn u8_arrays_as_bytes(&mut self) {
+        for msg in &mut self.messages {
+            for field in &mut msg.fields {
+                if let MavType::Array(t, _) = &field.mavtype {
+                    field.bytes = **t == MavType::UInt8 && field.enumtype.is_none();
+                }
+            }
+        }
+    }
+
+    /// Drop the messages and enums that are not selected by `filter`.
+    pub fn retain(&mut self, filter: &MessageFilter) {
+        self.messages
+            .retain(|msg| filter.messages.contains(&msg.raw_name));
+        self.enums.retain(|e| filter.enums.contains(&e.raw_name));
+    }
+
+    /// Definition files included by this profile, directly or through other
+    /// includes, each only once.
+    pub fn all_includes(&self, modules: &HashMap<String, MavProfile>) -> Vec<String> {
+        let mut includes: Vec<String> = vec![];
+        for inc in &self.includes {
+            let inc_profile = modules
+                .get(inc)
+                .unwrap_or_else(|| panic!("Module {} not loaded!", inc));
+            for name in std::iter::once(inc.clone()).chain(inc_profile.all_includes(modules)) {
+                if !includes.contains(&name) {
+                    includes.push(name);
+                }
+            }
+        }
+        includes
+    }
+
+    /// All messages of this profile followed by the messages of its includes
+    /// (recursively), each message id only once.
+    pub fn all_messages(&self, modules: &HashMap<String, MavProfile>) -> Vec<MavMessage> {
+        let mut messages = self.messages.clone();
+        for inc in &self.includes {
+            let inc_profile = modules
+                .get(inc)
+                .unwrap_or_else(|| panic!("Module {} not loaded!", inc));
+            for msg in inc_profile.all_messages(modules) {
+                if !messages.iter().any(|m| m.id == msg.id) {
+                    messages.push(msg);
+                }
+            }
+        }
+        messages
+    }
+
+    //TODO verify this is no longer necessary since we're supporting both mavlink1 and mavlink2
+    //    ///If we are not using Mavlink v2, remove messages with id's > 254
+    //    fn update_messages(mut self) -> Self {
+    //        //println!("Updating messages");
+    //        let msgs = self.messages.into_iter().filter(
+    //            |x| x.id <= 254).collect::<Vec<MavMessage>>();
+    //        self.messages = msgs;
+    //        self
+    //    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MavEnum {
+    pub name: String,
+    pub raw_name: String,
+    pub description: Option<String>,
+    pub entries: Vec<MavEnumEntry>,
+    /// If contains Some, the string represents the type witdh for bitflags
+    pub bitfield: Option<String>,
+    pub deprecated: Option<MavDeprecated>,
+}
+
+impl MavEnum {
+    /// The entry `entry` is an alias of, the first entry with its value.
+    /// prost only generates a variant for that first one.
+    pub fn alias_of(&self, entry: &MavEnumEntry) -> Option<&MavEnumEntry> {
+        entry.value?;
+        self.entries
+            .iter()
+            .take_while(|first| !std::ptr::eq(*first, entry))
+            .find(|first| first.value == entry.value)
+    }
+
+    /// Whether entries share values, the proto enum needs `allow_alias`.
+    pub fn has_aliases(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| self.alias_of(entry).is_some())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MavEnumEntry {
+    pub value: Option<u32>,
+    pub name: String,
+    pub raw_name: String,
+    pub description: Option<String>,
+    /// The `<param>`s of a MAV_CMD entry in index order, there can be gaps.
+    pub params: Option<Vec<MavCmdParam>>,
+    pub deprecated: Option<MavDeprecated>,
+}
+
+/// `<param>` of a MAV_CMD entry.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MavCmdParam {
+    /// 1 based, as the param fields of COMMAND_LONG.
+    pub index: usize,
+    pub label: Option<String>,
+    pub description: String,
+    pub units: Option<String>,
+    /// MAVLink name of the enum the values come from.
+    pub enumtype: Option<String>,
+    pub min_value: Option<f32>,
+    pub max_value: Option<f32>,
+    pub increment: Option<f32>,
+    pub reserved: bool,
+    /// Value to send for a reserved param, 0 or NaN.
+    pub default: Option<f32>,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MavMessage {
+    pub id: u32,
+    pub name: String,
+    pub raw_name: String,
+    pub description: Option<String>,
+    pub fields: Vec<MavField>,
+    pub deprecated: Option<MavDeprecated>,
+    /// The XML has an `<extensions/>` marker, also when the extension fields
+    /// are left out (without the emit-extensions feature).
+    pub has_extensions: bool,
+}
+
+/// `<deprecated>` marker of a message, enum or enum entry.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MavDeprecated {
+    pub since: String,
+    pub replaced_by: String,
+    pub text: Option<String>,
+}
+
+impl MavDeprecated {
+    /// Note for `#[deprecated]` and the proto comments, on one line.
+    pub fn note(&self) -> String {
+        let mut note = format!("since {}", self.since);
+        if !self.replaced_by.is_empty() {
+            note.push_str(&format!(", replaced by {}", self.replaced_by));
+        }
+        if let Some(text) = &self.text {
+            note.push_str(". ");
+            note.push_str(&text.split_whitespace().collect::<Vec<&str>>().join(" "));
+        }
+        note
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MavField {
+    pub mavtype: MavType,
+    pub name: String,
+    pub raw_name: String,
+    pub description: Option<String>,
+    pub enumtype: Option<String>,
+    pub raw_enumtype: Option<String>,
+    pub display: Option<String>,
+    pub units: Option<String>,
+    pub invalid: Option<InvalidValue>,
+    /// Array only invalid when all elements are the sentinel (`[UINT16_MAX]`),
+    /// otherwise the first element decides (`[NaN:]`).
+    pub invalid_all_elements: bool,
+    /// uint8_t array stored as proto `bytes` (`Vec<u8>`).
+    pub bytes: bool,
+    pub is_extension: bool,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MavType {
+    UInt8MavlinkVersion,
+    UInt8,
+    UInt16,
+    UInt32,
+    UInt64,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Char,
+    Float,
+    Double,
+    Array(Box<MavType>, usize),
+}
+
+impl Default for MavType {
+    fn default() -> MavType {
+        MavType::UInt8
+    }
+}
+
+impl MavType {
+    pub fn parse_type(s: &str) -> Option<MavType> {
+        use self::MavType::*;
+        match s {
+            "uint8_t_mavlink_version" => Some(UInt8MavlinkVersion),
+            "uint8_t" => Some(UInt8),
+            "uint16_t" => Some(UInt16),
+            "uint32_t" => Some(UInt32),
+            "uint64_t" => Some(UInt64),
+            "int8_t" => Some(Int8),
+            "int16_t" => Some(Int16),
+            "int32_t" => Some(Int32),
+            "int64_t" => Some(Int64),
+            "char" => Some(Char),
+            "float" => Some(Float),
+            "Double" => Some(Double),
+            "double" => Some(Double),
+            _ => {
+                if s.ends_with(']') {
+                    let start = s.find('[')?;
+                    let size = s[start + 1..(s.len() - 1)].parse::<usize>().ok()?;
+                    let mtype = MavType::parse_type(&s[0..start])?;
+                    Some(Array(Box::new(mtype), size))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Size of a given Mavtype
+    pub fn len(&self) -> usize {
+        use self::MavType::*;
+        match self.clone() {
+            UInt8MavlinkVersion | UInt8 | Int8 | Char => 1,
+            UInt16 | Int16 => 2,
+            UInt32 | Int32 | Float => 4,
+            UInt64 | Int64 | Double => 8,
+            Array(t, size) => t.len() * size,
+        }
+    }
+
+    /// Used for ordering of types
+    pub fn order_len(&self) -> usize {
+        use self::MavType::*;
+        match self.clone() {
+            UInt8MavlinkVersion | UInt8 | Int8 | Char => 1,
+            UInt16 | Int16 => 2,
+            UInt32 | Int32 | Float => 4,
+            UInt64 | Int64 | Double => 8,
+            Array(t, _) => t.len(),
+        }
+    }
+
+    /// Used for crc calculation
+    pub fn primitive_type(&self) -> String {
+        use self::MavType::*;
+        match self.clone() {
+            UInt8MavlinkVersion => "uint8_t".into(),
+            UInt8 => "uint8_t".into(),
+            Int8 => "int8_t".into(),
+            Char => "char".into(),
+            UInt16 => "uint16_t".into(),
+            Int16 => "int16_t".into(),
+            UInt32 => "uint32_t".into(),
+            Int32 => "int32_t".into(),
+            Float => "float".into(),
+            UInt64 => "uint64_t".into(),
+            Int64 => "int64_t".into(),
+            Double => "double".into(),
+            Array(t, _) => t.primitive_type(),
+        }
+    }
+
+    /// Used for proto annotations
+    pub fn mav_type(&self) -> String {
+        use self::MavType::*;
+        match self.clone() {
+            UInt8MavlinkVersion => "uint8_t".into(),
+            UInt8 => "uint8_t".into(),
+            Int8 => "int8_t".into(),
+            Char => "char".into(),
+            UInt16 => "uint16_t".into(),
+            Int16 => "int16_t".into(),
+            UInt32 => "uint32_t".into(),
+            Int32 => "int32_t".into(),
+            Float => "float".into(),
+            UInt64 => "uint64_t".into(),
+            Int64 => "int64_t".into(),
+            Double => "double".into(),
+            Array(t, s) => format!("{}[{}]", t.mav_type(), s),
+        }
+    }
+
+    /// Return rust equivalent of a given Mavtype
+    /// Used for generating struct fields.
+    pub fn rust_type(&self) -> String {
+        use self::MavType::*;
+        match self.clone() {
+            UInt8 | UInt8MavlinkVersion => "u8".into(),
+            Int8 => "i8".into(),
+            Char => "char".into(),
+            UInt16 => "u16".into(),
+            Int16 => "i16".into(),
+            UInt32 => "u32".into(),
+            Int32 => "i32".into(),
+            Float => "f32".into(),
+            UInt64 => "u64".into(),
+            Int64 => "i64".into(),
+            Double => "f64".into(),
+            Array(t, size) => format!("[{};{}]", t.rust_type(), size),
+        }
+    }
+
+    /// Value range of types narrower than the 32 bit proto/struct integer
+    /// they are stored in.
+    pub fn int_range(&self) -> Option<(i64, i64)> {
+        use self::MavType::*;
+        match self.clone() {
+            UInt8MavlinkVersion | UInt8 | Char => Some((0, u8::MAX as i64)),
+            UInt16 => Some((0, u16::MAX as i64)),
+            Int8 => Some((i8::MIN as i64, i8::MAX as i64)),
+            Int16 => Some((i16::MIN as i64, i16::MAX as i64)),
+            // char arrays are strings
+            Array(t, _) if *t == Char => None,
+            Array(t, _) => t.int_range(),
+            _ => None,
+        }
+    }
+
+    /// C name of the maximum of integer types, as used in the descriptions.
+    pub fn max_name(&self) -> Option<&'static str> {
+        use self::MavType::*;
+        match self {
+            UInt8 => Some("UINT8_MAX"),
+            UInt16 => Some("UINT16_MAX"),
+            UInt32 => Some("UINT32_MAX"),
+            UInt64 => Some("UINT64_MAX"),
+            Int8 => Some("INT8_MAX"),
+            Int16 => Some("INT16_MAX"),
+            Int32 => Some("INT32_MAX"),
+            Int64 => Some("INT64_MAX"),
+            _ => None,
+        }
+    }
+
+    /// Rust type prost uses for this (scalar) type in the proto structs.
+    pub fn proto_rust_type(&self) -> String {
+        use self::MavType::*;
+        match self.clone() {
+            UInt8 | UInt8MavlinkVersion | Char | UInt16 | UInt32 => "u32".into(),
+            Int8 | Int16 | Int32 => "i32".into(),
+            Float => "f32".into(),
+            UInt64 => "u64".into(),
+            Int64 => "i64".into(),
+            Double => "f64".into(),
+            Array(t, _) => t.proto_rust_type(),
+        }
+    }
+
+    /// Compare two MavTypes
+    pub fn compare(&self, other: &Self) -> Ordering {
+        let len = self.order_len();
+        (-(len as isize)).cmp(&(-(other.order_len() as isize)))
+    }
+}
+
+/// Sentinel a field is set to when its value is unknown or unused.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum InvalidValue {
+    /// The maximum of the MAVLink type (UINT16_MAX, INT32_MAX, ...).
+    Max,
+    NaN,
+    Int(i64),
+    Float(f64),
+}
+
+impl InvalidValue {
+    /// Parse the `invalid` attribute of a field (brackets of arrays and the
+    /// `:` of first-element sentinels already stripped), e.g. `UINT16_MAX`,
+    /// `NaN`, `0` or `-1`.
+    pub fn from_attribute(mavtype: &MavType, value: &str) -> Option<InvalidValue> {
+        let scalar = match mavtype {
+            MavType::Array(t, _) => t.as_ref(),
+            t => t,
+        };
+        let is_float = matches!(scalar, MavType::Float | MavType::Double);
+        let is_unsigned = matches!(
+            scalar,
+            MavType::UInt8 | MavType::UInt16 | MavType::UInt32 | MavType::UInt64
+        );
+        match value.trim() {
+            "NaN" if is_float => Some(InvalidValue::NaN),
+            value if Some(value) == scalar.max_name() => Some(InvalidValue::Max),
+            // strings are no arrays of numbers in the proto structs
+            _ if *scalar == MavType::Char => None,
+            value if is_float => value.parse().ok().map(InvalidValue::Float),
+            value => value
+                .parse()
+                .ok()
+                .filter(|value| !is_unsigned || *value >= 0)
+                .map(InvalidValue::Int),
+        }
+    }
+
+    /// Find the sentinel in descriptions like "If unknown, set to: UINT16_MAX"
+    /// or "(NaN if unknown)", it has to match the type of the field.
+    pub fn from_description(mavtype: &MavType, description: &str) -> Option<InvalidValue> {
+        const CUES: &[&str] = &[
+            "unknown",
+            "invalid",
+            "unused",
+            "not used",
+            "not known",
+            "not sent",
+            "ignore",
+        ];
+        let scalar = match mavtype {
+            MavType::Array(t, _) => t.as_ref(),
+            t => t,
+        };
+        let max_name = scalar.max_name();
+        let is_float = matches!(scalar, MavType::Float | MavType::Double);
+        description.split('.').find_map(|sentence| {
+            let lower = sentence.to_lowercase();
+            if !CUES.iter().any(|cue| lower.contains(cue)) {
+                return None;
+            }
+            sentence
+                .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .find_map(|word| match word {
+                    "NaN" if is_float => Some(InvalidValue::NaN),
+                    word if Some(word) == max_name => Some(InvalidValue::Max),
+                    _ => None,
+                })
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type"))]
+pub enum MavXmlElement {
+    Version,
+    Mavlink,
+    Dialect,
+    Include,
+    Enums,
+    Enum,
+    Entry,
+    Description,
+    Param,
+    Messages,
+    Message,
+    Field,
+    Deprecated,
+    Wip,
+    Extensions,
+}
+
+fn identify_element(s: &str) -> Option<MavXmlElement> {
+    use self::MavXmlElement::*;
+    match s {
+        "version" => Some(Version),
+        "mavlink" => Some(Mavlink),
+        "dialect" => Some(Dialect),
+        "include" => Some(Include),
+        "enums" => Some(Enums),
+        "enum" => Some(Enum),
+        "entry" => Some(Entry),
+        "description" => Some(Description),
+        "param" => Some(Param),
+        "messages" => Some(Messages),
+        "message" => Some(Message),
+        "field" => Some(Field),
+        "deprecated" => Some(Deprecated),
+        "wip" => Some(Wip),
+        "extensions" => Some(Extensions),
+        _ => None,
+    }
+}
+
+fn is_valid_parent(p: Option<MavXmlElement>, s: MavXmlElement) -> bool {
+    use self::MavXmlElement::*;
+    match s {
+        Version => p == Some(Mavlink),
+        Mavlink => p == None,
+        Dialect => p == Some(Mavlink),
+        Include => p == Some(Mavlink),
+        Enums => p == Some(Mavlink),
+        Enum => p == Some(Enums),
+        Entry => p == Some(Enum),
+        Description => p == Some(Entry) || p == Some(Message) || p == Some(Enum),
+        Param => p == Some(Entry),
+        Messages => p == Some(Mavlink),
+        Message => p == Some(Messages),
+        Field => p == Some(Message),
+        Deprecated => p == Some(Entry) || p == Some(Message) || p == Some(Enum),
+        Wip => p == Some(Entry) || p == Some(Message) || p == Some(Enum),
+        Extensions => p == Some(Message),
+    }
+}
+
+pub fn snake_name(name: &str) -> String {
+    let mut ident = name.to_snake_case();
+
+    // Use a raw identifier if the identifier matches a Rust keyword:
+    // https://doc.rust-lang.org/reference/keywords.html.
+    match ident.as_str() {
+        // 2015 strict keywords.
+        | "as" | "break" | "const" | "continue" | "else" | "enum" | "false"
+        | "fn" | "for" | "if" | "impl" | "in" | "let" | "loop" | "match" | "mod" | "move" | "mut"
+        | "pub" | "ref" | "return" | "static" | "struct" | "trait" | "true"
+        | "type" | "unsafe" | "use" | "where" | "while"
+        // 2018 strict keywords.
+        | "dyn"
+        // 2015 reserved keywords.
+        | "abstract" | "become" | "box" | "do" | "final" | "macro" | "override" | "priv" | "typeof"
+        | "unsized" | "virtual" | "yield"
+        // 2018 reserved keywords.
+        | "async" | "await" | "try" => ident.insert_str(0, "r#"),
+        // the following keywords are not supported as raw identifiers and are therefore suffixed with an underscore.
+        "self" | "super" | "extern" | "crate" => ident += "_",
+        _ => (),
+    }
+    ident
+}
+
+pub fn rusty_name(name: &str) -> String {
+    let mut ident = name.to_camel_case();
+
+    // Suffix an underscore for the `Self` Rust keyword as it is not allowed as raw identifier.
+    if ident == "Self" {
+        ident += "_";
+    }
+    ident
+}
+
+/// Allowlist of messages to generate, with the enums those messages use.
+#[derive(Debug, Clone)]
+pub struct MessageFilter {
+    pub messages: HashSet<String>,
+    pub enums: HashSet<String>,
+}
+
+impl MessageFilter {
+    /// Enums can be used by messages of other dialects (including dialects
+    /// use the enums of common.xml), so look at every definition file.
+    pub fn new(messages: &[String], definitions_dirs: &[PathBuf]) -> MessageFilter {
+        let messages: HashSet<String> = messages.iter().cloned().collect();
+        let mut enums = HashSet::new();
+        let mut found = HashSet::new();
+        for dir in definitions_dirs {
+            for entry in read_dir(dir).expect("could not read definitions directory") {
+                let path = entry.expect("could not read directory entry").path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("xml") {
+                    continue;
+                }
+                let profile = parse_profile(&mut File::open(&path).unwrap());
+                for msg in profile.messages {
+                    if messages.contains(&msg.raw_name) {
+                        enums.extend(msg.fields.iter().filter_map(|f| f.raw_enumtype.clone()));
+                        found.insert(msg.raw_name);
+                    }
+                }
+            }
+        }
+        let mut missing = messages.difference(&found).collect::<Vec<&String>>();
+        missing.sort();
+        for name in missing {
+            println!("cargo:warning=allowed message {} is not defined", name);
+        }
+        MessageFilter { messages, enums }
+    }
+}
+
+pub fn parse_profile(file: &mut dyn Read) -> MavProfile {
+    let mut stack: Vec<MavXmlElement> = vec![];
+
+    let mut profile = MavProfile {
+        includes: vec![],
+        messages: vec![],
+        enums: vec![],
+        version: None,
+        dialect: None,
+    };
+
+    let mut field = MavField::default();
+    let mut message = MavMessage::default();
+    let mut mavenum = MavEnum::default();
+    let mut entry = MavEnumEntry::default();
+    let mut include = String::new();
+    let mut deprecated = MavDeprecated::default();
+    let mut invalid: Option<String> = None;
+    let mut param = MavCmdParam::default();
+
+    let mut xml_filter = MavXmlFilter::default();
+    let mut parser: Vec<Result<XmlEvent, xml::reader::Error>> =
+        EventReader::new(file).into_iter().collect();
+    xml_filter.filter(&mut parser);
+    let mut is_in_extension = false;
+    for e in parser {
+        match e {
+            Ok(XmlEvent::StartElement {
+                name,
+                attributes: attrs,
+                ..
+            }) => {
+                let id = match identify_element(&name.to_string()) {
+                    None => {
+                        panic!("unexpected element {:?}", name);
+                    }
+                    Some(kind) => kind,
+                };
+
+                //
+                if !is_valid_parent(stack.last().copied(), id) {
+                    panic!("not valid parent {:?} of {:?}", stack.last(), id);
+                }
+
+                match id {
+                    MavXmlElement::Extensions => {
+                        is_in_extension = true;
+                        message.has_extensions = true;
+                    }
+                    MavXmlElement::Message => {
+                        message = Default::default();
+                    }
+                    MavXmlElement::Field => {
+                        field = Default::default();
+                        field.is_extension = is_in_extension;
+                        invalid = None;
+                    }
+                    MavXmlElement::Enum => {
+                        mavenum = Default::default();
+                    }
+                    MavXmlElement::Entry => {
+                        entry = Default::default();
+                    }
+                    MavXmlElement::Include => {
+                        include = Default::default();
+                    }
+                    MavXmlElement::Param => {
+                        param = Default::default();
+                    }
+                    MavXmlElement::Deprecated => {
+                        deprecated = Default::default();
+                    }
+                    _ => (),
+                }
+
+                stack.push(id);
+
+                for attr in attrs {
+                    match stack.last() {
+                        Some(&MavXmlElement::Enum) => {
+                            if attr.name.local_name == "name" {
+                                mavenum.raw_name = attr.value.clone();
+                                mavenum.name = rusty_name(&attr.value);
+                            }
+                            if attr.name.local_name == "bitmask" && attr.value == "true" {
+                                mavenum.bitfield = Some("u32".into());
+                            }
+                        }
+                        Some(&MavXmlElement::Entry) => {
+                            match attr.name.local_name.clone().as_ref() {
+                                "name" => {
+                                    entry.raw_name = attr.value.clone();
+                                    let name = rusty_name(&attr.value);
+                                    entry.name = if let Some(n) = name.strip_prefix(&mavenum.name) {
+                                        if let Some(ch) = n.chars().next() {
+                                            if ch.is_alphabetic() {
+                                                n.to_string()
+                                            } else {
+                                                name
+                                            }
+                                        } else {
+                                            name
+                                        }
+                                    } else {
+                                        name
+                                    }
+                                }
+                                "value" => {
+                                    // Deal with hexadecimal numbers
+                                    if attr.value.starts_with("0x") {
+                                        entry.value = Some(
+                                            u32::from_str_radix(
+                                                attr.value.trim_start_matches("0x"),
+                                                16,
+                                            )
+                                            .unwrap(),
+                                        );
+                                    } else {
+                                        entry.value = Some(attr.value.parse::<u32>().unwrap());
+                                    }
+                                }
+                                _ => (),
+                            }
+                        }
+                        Some(&MavXmlElement::Message) => {
+                            match attr.name.local_name.clone().as_ref() {
+                                "name" => {
+                                    message.raw_name = attr.value.clone();
+                                    message.name = rusty_name(&attr.value);
+                                }
+                                "id" => {
+                                    //message.id = attr.value.parse::<u8>().unwrap();
+                                    message.id = attr.value.parse::<u32>().unwrap();
+                                }
+                                _ => (),
+                            }
+                        }
+                        Some(&MavXmlElement::Field) => {
+                            match attr.name.local_name.clone().as_ref() {
+                                "name" => {
+                                    field.raw_name = attr.value.clone();
+                                    field.name = snake_name(&attr.value);
+                                }
+                                "type" => {
+                                    field.mavtype = MavType::parse_type(&attr.value).unwrap();
+                                }
+                                "enum" => {
+                                    field.raw_enumtype = Some(attr.value.clone());
+                                    field.enumtype = Some(rusty_name(&attr.value));
+                                }
+                                "display" => {
+                                    field.display = Some(attr.value);
+                                }
+                                "units" => {
+                                    field.units = Some(attr.value);
+                                }
+                                "invalid" => {
+                                    invalid = Some(attr.value);
+                                }
+                                _ => (),
+                            }
+                        }
+                        Some(&MavXmlElement::Deprecated) => match attr.name.local_name.as_ref() {
+                            "since" => deprecated.since = attr.value,
+                            "replaced_by" => deprecated.replaced_by = attr.value,
+                            _ => (),
+                        },
+                        Some(&MavXmlElement::Param) => match attr.name.local_name.as_ref() {
+                            "index" => param.index = attr.value.parse::<usize>().unwrap(),
+                            "label" => param.label = Some(attr.value),
+                            "units" => param.units = Some(attr.value),
+                            "enum" => param.enumtype = Some(attr.value),
+                            "minValue" => param.min_value = attr.value.parse::<f32>().ok(),
+                            "maxValue" => param.max_value = attr.value.parse::<f32>().ok(),
+                            "increment" => param.increment = attr.value.parse::<f32>().ok(),
+                            "reserved" => param.reserved = attr.value == "true",
+                            "default" => param.default = attr.value.parse::<f32>().ok(),
+                            _ => (),
+                        },
+                        _ => (),
+                    }
+                }
+            }
+            Ok(XmlEvent::Characters(s)) => {
+                use self::MavXmlElement::*;
+                match (stack.last(), stack.get(stack.len() - 2)) {
+                    (Some(&Description), Some(&Message)) => {
+                        message.description = Some(s.replace("\t", "    "));
+                    }
+                    (Some(&Field), Some(&Message)) => {
+                        field.description = Some(s.replace("\t", "    "));
+                    }
+                    (Some(&Description), Some(&Enum)) => {
+                        mavenum.description = Some(s.replace("\t", "    "));
+                    }
+                    (Some(&Description), Some(&Entry)) => {
+                        entry.description = Some(s.replace("\t", "    "));
+                    }
+                    (Some(&Param), Some(&Entry)) => {
+                        param.description = s;
+                    }
+                    (Some(&Include), Some(&Mavlink)) => {
+                        include = s.replace("\n", "");
+                    }
+                    (Some(&Version), Some(&Mavlink)) => {
+                        profile.version = Some(s.trim().to_string());
+                    }
+                    (Some(&Dialect), Some(&Mavlink)) => {
+                        let dialect = s
+                            .trim()
+                            .parse::<u8>()
+                            .unwrap_or_else(|_| panic!("invalid dialect number {:?}", s));
+                        profile.dialect = Some(dialect);
+                    }
+                    (Some(Deprecated), _) => {
+                        deprecated.text = Some(s);
+                    }
+                    data => {
+                        panic!("unexpected text data {:?} reading {:?}", data, s);
+                    }
+                }
+            }
+            Ok(XmlEvent::EndElement { .. }) => {
+                match stack.last() {
+                    Some(&MavXmlElement::Deprecated) => match stack.get(stack.len() - 2) {
+                        Some(&MavXmlElement::Message) => {
+                            message.deprecated = Some(deprecated.clone())
+                        }
+                        Some(&MavXmlElement::Enum) => mavenum.deprecated = Some(deprecated.clone()),
+                        Some(&MavXmlElement::Entry) => entry.deprecated = Some(deprecated.clone()),
+                        _ => (),
+                    },
+                    Some(&MavXmlElement::Field) => {
+                        // the attribute wins over the description, arrays
+                        // are written "[UINT16_MAX]" (all elements) or
+                        // "[NaN:]" (first element)
+                        if let Some(value) = &invalid {
+                            let value = match value
+                                .strip_prefix('[')
+                                .and_then(|value| value.strip_suffix(']'))
+                            {
+                                Some(value) => match value.strip_suffix(':') {
+                                    Some(value) => value,
+                                    None => {
+                                        field.invalid_all_elements = true;
+                                        value
+                                    }
+                                },
+                                None => value,
+                            };
+                            field.invalid = InvalidValue::from_attribute(&field.mavtype, value);
+                        }
+                        if field.invalid.is_none() {
+                            if let Some(description) = &field.description {
+                                field.invalid =
+                                    InvalidValue::from_description(&field.mavtype, description);
+                            }
+                        }
+                        message.fields.push(field.clone());
+                    }
+                    Some(&MavXmlElement::Param) => {
+                        let params = entry.params.get_or_insert_with(Vec::new);
+                        params.retain(|p| p.index != param.index);
+                        params.push(param.clone());
+                        params.sort_by_key(|p| p.index);
+                    }
+                    Some(&MavXmlElement::Entry) => {
+                        mavenum.entries.push(entry.clone());
+                    }
+                    Some(&MavXmlElement::Message) => {
+                        is_in_extension = false;
+                        // Follow mavlink ordering specification: https://mavlink.io/en/guide/serialization.html#field_reordering
+                        let mut not_extension_fields = message.fields.clone();
+                        let mut extension_fields = message.fields.clone();
+
+                        not_extension_fields.retain(|field| !field.is_extension);
+                        extension_fields.retain(|field| field.is_extension);
+
+                        // Only not mavlink 1 fields need to be sorted
+                        not_extension_fields.sort_by(|a, b| a.mavtype.compare(&b.mavtype));
+
+                        // Update msg fields and add the new message
+                        let mut msg = message.clone();
+                        msg.fields.clear();
+                        msg.fields.extend(not_extension_fields);
+                        msg.fields.extend(extension_fields);
+
+                        profile.messages.push(msg);
+                    }
+                    Some(&MavXmlElement::Enum) => {
+                        profile.enums.push(mavenum.clone());
+                    }
+                    Some(&MavXmlElement::Include) => {
+                        profile.includes.push(include.clone());
+                    }
+                    _ => (),
+                }
+                stack.pop();
+            }
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    //let profile = profile.update_messages(); //TODO verify no longer needed
+    profile.update_enums()
+}
+
+fn merge_enums(profile: &mut MavProfile, modules: &HashMap<String, MavProfile>) {
+    // entries of another name with the value of an entry become aliases
+    fn enum_contains(enums: &[MavEnumEntry], name: &str) -> bool {
+        enums.iter().any(|e| e.raw_name == name)
+    }
+    let mut missing: Vec<MavEnumEntry> = Vec::new();
+    for enum_val in &mut profile.enums {
+        for inc in &profile.includes {
+            for e2 in &modules
+                .get(inc)
+                .unwrap_or_else(|| panic!("Module {} not loaded!", inc))
+                .enums
+            {
+                if enum_val.name == e2.name {
+                    missing.append(
+                        &mut e2
+                            .entries
+                            .iter()
+                            .filter(|e| !enum_contains(&enum_val.entries, &e.raw_name))
+                            .cloned()
+                            .collect(),
+                    )
+                }
+            }
+        }
+        enum_val.entries.append(&mut missing);
+    }
+}
+
+/// Path of a definition file, includes are looked up in all definition
+/// directories, bundled first.  Differing copies in several directories are
+/// an error rather than silently using the first one.
+fn find_definition(definitions_dirs: &[PathBuf], definition_file: &OsStr) -> PathBuf {
+    let mut found = definitions_dirs
+        .iter()
+        .map(|dir| dir.join(definition_file))
+        .filter(|path| path.is_file());
+    let path = found.next().unwrap_or_else(|| {
+        panic!(
+            "{} not found in any definitions directory",
+            definition_file.to_string_lossy()
+        )
+    });
+    for other in found {
+        if std::fs::read(&path).ok() != std::fs::read(&other).ok() {
+            panic!(
+                "{} and {} differ, definition file names have to be unique across the definitions directories",
+                path.display(),
+                other.display()
+            );
+        }
+    }
+    path
+}
+
+/// Parse a definition file and (recursively) its includes into `modules`,
+/// keyed by file name.  The profiles get the entries of the enums they
+/// extend merged in.
+pub fn load(
+    definitions_dirs: &[PathBuf],
+    definition_file: &OsStr,
+    modules: &mut HashMap<String, MavProfile>,
+    filter: Option<&MessageFilter>,
+) {
+    let key = definition_file.to_string_lossy().to_string();
+    if modules.contains_key(&key) {
+        return;
+    }
+    let mut inf = File::open(find_definition(definitions_dirs, definition_file)).unwrap();
+
+    let mut profile = parse_profile(&mut inf);
+    if let Some(filter) = filter {
+        profile.retain(filter);
+    }
+    modules.insert(key.clone(), profile.clone());
+    for inc in &profile.includes {
+        let inc: OsString = inc.into();
+        load(definitions_dirs, &inc, modules, filter);
+    }
+    merge_enums(&mut profile, modules);
+    for inc in &profile.includes {
+        let inc_profile = modules
+            .get(inc)
+            .unwrap_or_else(|| panic!("Module {} not loaded!", inc));
+        profile.version = profile
+            .version
+            .take()
+            .or_else(|| inc_profile.version.clone());
+        profile.dialect = profile.dialect.or(inc_profile.dialect);
+    }
+    // includers merge from the merged enums, so an enum has the entries of
+    // every (transitive) include
+    modules.insert(key, profile);
+}
+
+/// Generate protobuf represenation of mavlink message set
+/// Generate rust representation of mavlink message set with appropriate conversion methods
+///
+/// Only reads `modules`, so dialects can be generated in parallel once they
+/// are all loaded.
+pub fn generate(
+    definitions_dirs: &[PathBuf],
+    definition_file: &OsStr,
+    out_dir: &Path,
+    modules: &HashMap<String, MavProfile>,
+    config: &BuildConfig,
+) {
+    let module_name = config.module_name(definition_file);
+    let profile = modules
+        .get(&*definition_file.to_string_lossy())
+        .expect("definition file not loaded");
+    let mut definition_rs = PathBuf::from(&module_name);
+    definition_rs.set_extension("rs");
+    let mut definition_proto = PathBuf::from(&module_name);
+    definition_proto.set_extension("proto");
+
+    let in_path = find_definition(definitions_dirs, definition_file);
+
+    let dest_path = Path::new(&out_dir)
+        .join("src")
+        .join("mavlink")
+        .join(definition_rs);
+    let mut proto_outf = {
+        let dest_path = Path::new(&out_dir).join("protos").join(definition_proto);
+        File::create(&dest_path).unwrap()
+    };
+
+    // proto file
+    write!(proto_outf, "syntax = \"proto3\";\n\n").unwrap();
+    write!(
+        proto_outf,
+        "package {};\n\n",
+        config.proto_package(&module_name)
+    )
+    .unwrap();
+    profile
+        .emit_proto(&mut proto_outf, profile, modules, config)
+        .unwrap();
+
+    // JSON schema next to it
+    let mut schema_outf = {
+        let dest_path = Path::new(&out_dir)
+            .join("protos")
+            .join(format!("{}.schema.json", module_name));
+        File::create(&dest_path).unwrap()
+    };
+    profile
+        .emit_schema(&mut schema_outf, &module_name, modules)
+        .unwrap();
+
+    // rust file
+    let rust_tokens = profile.emit_rust(&module_name, modules, config);
+    write_generated(&dest_path, rust_tokens.to_string().as_bytes());
+
+    // Re-run build if definition file changes
+    println!("cargo:rerun-if-changed={}", in_path.to_string_lossy());
+}
+
+#[cfg(not(feature = "emit-extensions"))]
+struct ExtensionFilter {
+    pub is_in: bool,
+}
+
+struct MavXmlFilter {
+    #[cfg(not(feature = "emit-extensions"))]
+    extension_filter: ExtensionFilter,
+}
+
+impl Default for MavXmlFilter {
+    fn default() -> MavXmlFilter {
+        MavXmlFilter {
+            #[cfg(not(feature = "emit-extensions"))]
+            extension_filter: ExtensionFilter { is_in: false },
+        }
+    }
+}
+
+impl MavXmlFilter {
+    pub fn filter(&mut self, elements: &mut Vec<Result<XmlEvent, xml::reader::Error>>) {
+        // List of filters
+        Self::filter_unknown(elements);
+        elements.retain(|x| self.filter_extension(x));
+        #[cfg(not(feature = "emit-wip"))]
+        Self::filter_wip(elements);
+    }
+
+    /// Skip elements newer than this parser (with everything in them), so
+    /// additions to the XML schema do not break the build.  Runs first, the
+    /// other filters only see known elements.
+    fn filter_unknown(elements: &mut Vec<Result<XmlEvent, xml::reader::Error>>) {
+        let mut keep = vec![true; elements.len()];
+        // start event of the outermost unknown element and the open elements
+        // inside it (including itself)
+        let mut unknown: Option<(usize, usize)> = None;
+        let mut open: Vec<String> = vec![];
+        let mut warned: Vec<String> = vec![];
+        for (i, element) in elements.iter().enumerate() {
+            match element {
+                Ok(XmlEvent::StartElement { name, .. }) => match &mut unknown {
+                    Some((_, depth)) => *depth += 1,
+                    None if identify_element(&name.to_string()).is_none() => {
+                        let name = name.to_string();
+                        if !warned.contains(&name) {
+                            println!(
+                                "cargo:warning=skipping unknown element <{}> in <{}>",
+                                name,
+                                open.last().map_or("", String::as_str)
+                            );
+                            warned.push(name);
+                        }
+                        unknown = Some((i, 1));
+                    }
+                    None => open.push(name.to_string()),
+                },
+                Ok(XmlEvent::EndElement { .. }) => match &mut unknown {
+                    Some((start, depth)) => {
+                        *depth -= 1;
+                        if *depth == 0 {
+                            keep[*start..=i].iter_mut().for_each(|k| *k = false);
+                            unknown = None;
+                        }
+                    }
+                    None => {
+                        open.pop();
+                    }
+                },
+                _ => (),
+            }
+        }
+        let mut keep = keep.into_iter();
+        elements.retain(|_| keep.next().unwrap());
+    }
+
+    /// Ignore messages and enum entries marked `<wip/>`.  The marker comes
+    /// after the start of the element, so this works on all the events.
+    #[cfg(not(feature = "emit-wip"))]
+    fn filter_wip(elements: &mut Vec<Result<XmlEvent, xml::reader::Error>>) {
+        let mut keep = vec![true; elements.len()];
+        // start event, element and whether it is WIP of the open elements
+        let mut open: Vec<(usize, MavXmlElement, bool)> = vec![];
+        for (i, element) in elements.iter().enumerate() {
+            match element {
+                Ok(XmlEvent::StartElement { name, .. }) => {
+                    let id = match identify_element(&name.to_string()) {
+                        None => {
+                            panic!("unexpected element {:?}", name);
+                        }
+                        Some(kind) => kind,
+                    };
+                    if id == MavXmlElement::Wip {
+                        if let Some((_, MavXmlElement::Message, wip))
+                        | Some((_, MavXmlElement::Entry, wip)) = open.last_mut()
+                        {
+                            *wip = true;
+                        }
+                    }
+                    open.push((i, id, false));
+                }
+                Ok(XmlEvent::EndElement { .. }) => {
+                    if let Some((start, _, true)) = open.pop() {
+                        keep[start..=i].iter_mut().for_each(|k| *k = false);
+                    }
+                }
+                _ => (),
+            }
+        }
+        let mut keep = keep.into_iter();
+        elements.retain(|_| keep.next().unwrap());
+    }
+
+    #[cfg(feature = "emit-extensions")]
+    pub fn filter_extension(
+        &mut self,
+        _element: &Result<xml::reader::XmlEvent, xml::reader::Error>,
+    ) -> bool {
+        return true;
+    }
+
+    /// Ignore extension fields
+    #[cfg(not(feature = "emit-extensions"))]
+    pub fn filter_extension(
+        &mut self,
+        element: &Result<xml::reader::XmlEvent, xml::reader::Error>,
+    ) -> bool {
+        match element {
+            Ok(content) => {
+                match content {
+                    XmlEvent::StartElement { name, .. } => {
+                        let id = match identify_element(&name.to_string()) {
+                            None => {
+                                panic!("unexpected element {:?}", name);
+                            }
+                            Some(kind) => kind,
+                        };
+                        if id == MavXmlElement::Extensions {
+                            // keep the marker for MavMessage::has_extensions
+                            self.extension_filter.is_in = true;
+                            return true;
+                        }
+                    }
+                    XmlEvent::EndElement { name } => {
+                        let id = match identify_element(&name.to_string()) {
+                            None => {
+                                panic!("unexpected element {:?}", name);
+                            }
+                            Some(kind) => kind,
+                        };
+
+                        if id == MavXmlElement::Message {
+                            self.extension_filter.is_in = false;
+                        } else if id == MavXmlElement::Extensions {
+                            return true;
+                        }
+                    }
+                    _ => {}
+                }
+                !self.extension_filter.is_in
+            }
+            Err(error) => panic!("Failed to filter XML: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// An empty directory below the temp dir, for this test only.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("proto-mav-build-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A bundled and an extra definitions directory, with `common.xml` in
+    /// the bundled one and `custom.xml` in the extra one.
+    fn definitions_dirs(name: &str) -> Vec<PathBuf> {
+        let root = test_dir(name);
+        let dirs = vec![root.join("bundled"), root.join("extra")];
+        for dir in &dirs {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(dirs[0].join("common.xml"), "<mavlink/>").unwrap();
+        fs::write(dirs[1].join("custom.xml"), "<mavlink/>").unwrap();
+        dirs
+    }
+
+    #[test]
+    fn test_find_definition() {
+        let dirs = definitions_dirs("find");
+        // an identical copy is fine, the bundled one is used
+        fs::write(dirs[1].join("common.xml"), "<mavlink/>").unwrap();
+        assert_eq!(
+            find_definition(&dirs, OsStr::new("common.xml")),
+            dirs[0].join("common.xml")
+        );
+        assert_eq!(
+            find_definition(&dirs, OsStr::new("custom.xml")),
+            dirs[1].join("custom.xml")
+        );
+        fs::remove_dir_all(dirs[0].parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "differ, definition file names have to be unique")]
+    fn test_find_definition_differing_copies() {
+        let dirs = definitions_dirs("differ");
+        fs::write(
+            dirs[1].join("common.xml"),
+            "<mavlink><version>3</version></mavlink>",
+        )
+        .unwrap();
+        find_definition(&dirs, OsStr::new("common.xml"));
+    }
+
+    #[test]
+    #[should_panic(expected = "not found in any definitions directory")]
+    fn test_find_definition_missing() {
+        let dirs = definitions_dirs("missing");
+        find_definition(&dirs, OsStr::new("minimal.xml"));
+    }
+
+    #[test]
+    fn test_filter_unknown() {
+        let xml = r#"<?xml version="1.0"?>
+<mavlink>
+  <version>3</version>
+  <signing_keys>
+    <key name="test">no schema knows me</key>
+  </signing_keys>
+  <enums>
+    <enum name="TEST_ENUM">
+      <entry value="1" name="TEST_ENUM_ONE">
+        <description>One.</description>
+        <future_marker/>
+      </entry>
+    </enum>
+  </enums>
+  <messages>
+    <message id="42000" name="TEST_MESSAGE">
+      <description>A message with an element from the future.</description>
+      <field type="uint8_t" name="small">Small.</field>
+      <layout kind="packed">
+        <field type="uint32_t" name="hidden">Inside the unknown element.</field>
+      </layout>
+      <field type="uint16_t" name="large">Large.</field>
+    </message>
+  </messages>
+</mavlink>
+"#;
+        let profile = parse_profile(&mut xml.as_bytes());
+        assert_eq!(profile.version.as_deref(), Some("3"));
+        assert_eq!(profile.enums.len(), 1);
+        assert_eq!(profile.enums[0].entries[0].raw_name, "TEST_ENUM_ONE");
+        let message = &profile.messages[0];
+        assert_eq!(message.raw_name, "TEST_MESSAGE");
+        let fields = message
+            .fields
+            .iter()
+            .map(|field| field.raw_name.as_str())
+            .collect::<Vec<_>>();
+        // the field inside <layout> is skipped with it
+        assert_eq!(fields, ["large", "small"]);
+    }
+}
@@ -4,19 +4,20 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::u32;
 
+use crate::config::BuildConfig;
 use crate::parser::*;
-use crate::util::to_module_name;
 
 impl MavProfile {
     pub fn emit_proto(
         &self,
         outf: &mut dyn Write,
         profile: &MavProfile,
-        modules: &mut HashMap<String, MavProfile>,
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
     ) -> io::Result<()> {
         writeln!(outf, "import \"mav.proto\";\n")?;
         for inc in &self.includes {
-            let inc_name = to_module_name(&inc);
+            let inc_name = config.module_name(inc);
             let mut inc_proto = PathBuf::from(&inc_name);
             inc_proto.set_extension("proto");
             writeln!(outf, "import \"{}\";", inc_proto.to_string_lossy())?;
@@ -27,20 +28,49 @@ impl MavProfile {
         }
         for message in &self.messages {
             writeln!(outf)?;
-            message.emit_proto(outf, profile, modules)?;
+            message.emit_proto(outf, profile, modules, config)?;
         }
         Ok(())
     }
+
+    /// `#[deprecated]` on the prost types and enum variants the XML marks as
+    /// deprecated, `package` is the proto package of this profile.
+    pub fn apply_deprecations(&self, package: &str, prost_config: &mut prost_build::Config) {
+        fn attribute(deprecated: &MavDeprecated) -> String {
+            format!("#[deprecated(note = {:?})]", deprecated.note())
+        }
+
+        for message in &self.messages {
+            if let Some(deprecated) = &message.deprecated {
+                let path = format!(".{}.{}", package, message.raw_name);
+                prost_config.type_attribute(path, attribute(deprecated));
+            }
+        }
+        for e in &self.enums {
+            if let Some(deprecated) = &e.deprecated {
+                let path = format!(".{}.{}", package, e.raw_name);
+                prost_config.type_attribute(path, attribute(deprecated));
+            }
+            for entry in &e.entries {
+                if let Some(deprecated) = &entry.deprecated {
+                    let path = format!(".{}.{}.{}", package, e.raw_name, entry.raw_name);
+                    prost_config.field_attribute(path, attribute(deprecated));
+                }
+            }
+        }
+    }
 }
 
 impl MavEnum {
     fn emit_proto(&self, outf: &mut dyn Write) -> io::Result<()> {
-        writeln!(outf, "enum {} {{", self.raw_name)?;
         if let Some(description) = &self.description {
             for d in description.split('\n') {
                 writeln!(outf, "// {}", d.trim())?;
             }
         }
+        if let Some(deprecated) = &self.deprecated {
+            writeln!(outf, "// Deprecated {}", deprecated.note())?;
+        }
         let bits = if self.bitfield.is_some() {
             writeln!(
                 outf,
@@ -50,6 +80,11 @@ impl MavEnum {
         } else {
             false
         };
+        writeln!(outf, "enum {} {{", self.raw_name)?;
+        if self.has_aliases() {
+            // prost keeps the first entry of a value, see emit_enum_mav_names
+            writeln!(outf, "  option allow_alias = true;")?;
+        }
         let mut sorted = self.entries.clone();
         sorted.sort_by(|a, b| {
             if a.value.is_none() && b.value.is_none() {
@@ -80,7 +115,6 @@ impl MavEnum {
                 }
             }
         }
-        let mut comment_field = false;
         for (i, field) in sorted.iter().enumerate() {
             if i == 0 && !has_zero && max_val != 0 {
                 // Do not have a 0 based enum field but protbuf requires it.
@@ -95,49 +129,68 @@ impl MavEnum {
                     writeln!(outf, "  // {}", d)?;
                 }
             }
-            if bits {
-                let mut v: u32 = field.value.expect("No value for a bitfield!");
-                let mut i = 1;
-                let mut found = false;
-                while v > 0 && i <= 32 && !found {
-                    if (v >> (i - 1)) == 1 {
-                        v = i;
-                        found = true;
+            if let Some(params) = &field.params {
+                // a leading comment, prost makes it the doc of the variant
+                writeln!(outf, "  //\n  // Params:\n  //")?;
+                // Some messages can jump between values, like:
+                // 0, 1, 2, 7
+                let count = params.last().map_or(0, |p| p.index);
+                for index in 1..=count {
+                    match params.iter().find(|p| p.index == index) {
+                        Some(p) if p.reserved => writeln!(outf, "  // - param{}: reserved", index)?,
+                        Some(p) => {
+                            let mut name = format!("param{}", index);
+                            let extra = p
+                                .label
+                                .iter()
+                                .chain(p.units.iter().chain(p.enumtype.iter()))
+                                .map(|s| s.as_str())
+                                .collect::<Vec<_>>();
+                            if !extra.is_empty() {
+                                name = format!("{} ({})", name, extra.join(", "));
+                            }
+                            let description = p
+                                .description
+                                .split_whitespace()
+                                .collect::<Vec<_>>()
+                                .join(" ");
+                            writeln!(outf, "  // - {}: {}", name, description)?
+                        }
+                        None => writeln!(
+                            outf,
+                            "  // - param{}: not used, 0 unless the requesting message defines it",
+                            index
+                        )?,
                     }
-                    i += 1;
                 }
-                if !found && v != 0 {
-                    return Err(io::Error::new(
-                        io::ErrorKind::Other,
-                        "Invalid bitfield, not a power of 2.",
-                    ));
-                }
-                writeln!(outf, "  // bit {}", v)?;
-            }
-            let val = field.value.unwrap_or(max_val + i as u32);
-            if (val & 0x80000000) != 0 {
-                comment_field = true;
             }
-            if comment_field {
-                println!(
-                    "WARNING: enum value to large for protobuf, {}.{}",
-                    self.raw_name, field.raw_name
-                );
-                writeln!(outf, "  // enum value to large for protobuf")?;
-                write!(outf, "  //")?;
+            if let Some(deprecated) = &field.deprecated {
+                writeln!(outf, "  // Deprecated {}", deprecated.note())?;
             }
             if bits {
+                let v: u32 = field.value.expect("No value for a bitfield!");
+                if v == 0 || v.is_power_of_two() {
+                    let bit = if v == 0 { 0 } else { v.trailing_zeros() + 1 };
+                    writeln!(outf, "  // bit {}", bit)?;
+                } else {
+                    // combined entries, e.g. all bits of a group
+                    writeln!(outf, "  // several bits")?;
+                }
+            }
+            let val = field.value.unwrap_or(max_val + i as u32);
+            if val > i32::MAX as u32 {
+                // proto enums are int32, write the same bits as a negative
+                // value, `as u32` on the prost enum gives the MAVLink value
+                writeln!(
+                    outf,
+                    "  {} = {};  // MavLink value {:#010x}",
+                    field.raw_name, val as i32, val
+                )?;
+            } else if bits {
                 writeln!(outf, "  {} = {:#010x};", field.raw_name, val)?;
             } else {
                 writeln!(outf, "  {} = {};", field.raw_name, val)?;
             }
-            if let Some(params) = &field.params {
-                writeln!(outf, "  // ***** START Params")?;
-                for p in params {
-                    writeln!(outf, "  // {}", p)?;
-                }
-                writeln!(outf, "  // ***** END Params")?;
-            }
         }
         writeln!(outf, "}}")?;
         Ok(())
@@ -149,13 +202,17 @@ impl MavMessage {
         &self,
         outf: &mut dyn Write,
         profile: &MavProfile,
-        modules: &mut HashMap<String, MavProfile>,
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
     ) -> io::Result<()> {
         if let Some(description) = &self.description {
             for d in description.split('\n') {
                 writeln!(outf, "// {}", d.trim())?;
             }
         }
+        if let Some(deprecated) = &self.deprecated {
+            writeln!(outf, "// Deprecated {}", deprecated.note())?;
+        }
         writeln!(
             outf,
             "message {} {{  // MavLink id: {}",
@@ -163,7 +220,7 @@ impl MavMessage {
         )?;
         writeln!(outf, "  option (mav.message).id = {};", self.id)?;
         for (i, field) in self.fields.iter().enumerate() {
-            field.emit_proto(outf, i + 1, profile, modules)?;
+            field.emit_proto(outf, i + 1, profile, modules, config)?;
         }
         writeln!(outf, "}}")?;
         Ok(())
@@ -176,7 +233,8 @@ impl MavField {
         outf: &mut dyn Write,
         id: usize,
         profile: &MavProfile,
-        modules: &mut HashMap<String, MavProfile>,
+        modules: &HashMap<String, MavProfile>,
+        config: &BuildConfig,
     ) -> io::Result<()> {
         fn has_enum(enums: &[MavEnum], name: &str) -> Option<MavEnum> {
             for e in enums {
@@ -192,6 +250,10 @@ impl MavField {
                 writeln!(outf, "  // {}", d.trim())?;
             }
         }
+        if let Some(units) = &self.units {
+            writeln!(outf, "  // Units: {}", units)?;
+        }
+        writeln!(outf, "  // MavLink type: {}", self.mavtype.mav_type())?;
         let mut extras = String::new();
         if let Some(enum_type) = &self.enumtype {
             let raw_type = self.raw_enumtype.as_ref().unwrap();
@@ -221,8 +283,9 @@ impl MavField {
                     let p = modules.get(inc).unwrap();
                     if let Some(enm) = has_enum(&p.enums, enum_type) {
                         found = true;
-                        let inc_mod = to_module_name(&inc);
-                        extras.push_str(&format!(", enum: \"{}.{}\"", inc_mod, raw_type));
+                        let inc_mod = config.module_name(inc);
+                        let inc_package = config.proto_package(&inc_mod);
+                        extras.push_str(&format!(", enum: \"{}.{}\"", inc_package, raw_type));
                         if enm.bitfield.is_some() {
                             writeln!(
                                 outf,
@@ -240,7 +303,7 @@ impl MavField {
                             write!(
                                 outf,
                                 "  {}{}.{} {} = {}",
-                                rep, inc_mod, raw_type, self.raw_name, id
+                                rep, inc_package, raw_type, self.raw_name, id
                             )?;
                         }
                         break;
@@ -253,6 +316,8 @@ impl MavField {
                     ));
                 }
             }
+        } else if self.bytes {
+            write!(outf, "  bytes {} = {}", self.raw_name, id)?;
         } else {
             write!(
                 outf,
@@ -262,6 +327,11 @@ impl MavField {
                 id
             )?;
         }
+        if config.proto_int_ranges && !self.bytes {
+            if let Some((min, max)) = self.mavtype.int_range() {
+                extras.push_str(&format!(", min: {}, max: {}", min, max));
+            }
+        }
         writeln!(
             outf,
             " [(mav.opts) = {{ type: \"{}\"{} }}];",
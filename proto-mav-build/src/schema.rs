@@ -0,0 +1,305 @@
+//! JSON schema of the messages of a dialect, as serialized by serde from the
+//! prost structs (enum fields are their i32 values).
+
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::parser::*;
+
+/// Just enough JSON to write the schemas without pulling in serde_json.
+enum Json {
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn string<S: Into<String>>(s: S) -> Json {
+        Json::String(s.into())
+    }
+
+    fn number<N: ToString>(n: N) -> Json {
+        Json::Number(n.to_string())
+    }
+
+    fn object(entries: Vec<(&str, Json)>) -> Json {
+        Json::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    fn write(&self, out: &mut dyn Write, indent: usize) -> io::Result<()> {
+        let pad = "  ".repeat(indent + 1);
+        match self {
+            Json::Bool(b) => write!(out, "{}", b),
+            Json::Number(n) => write!(out, "{}", n),
+            Json::String(s) => write_string(out, s),
+            Json::Array(values) if values.is_empty() => write!(out, "[]"),
+            Json::Array(values) => {
+                writeln!(out, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    write!(out, "{}", pad)?;
+                    value.write(out, indent + 1)?;
+                    writeln!(out, "{}", if i + 1 < values.len() { "," } else { "" })?;
+                }
+                write!(out, "{}]", "  ".repeat(indent))
+            }
+            Json::Object(entries) if entries.is_empty() => write!(out, "{{}}"),
+            Json::Object(entries) => {
+                writeln!(out, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    write!(out, "{}", pad)?;
+                    write_string(out, key)?;
+                    write!(out, ": ")?;
+                    value.write(out, indent + 1)?;
+                    writeln!(out, "{}", if i + 1 < entries.len() { "," } else { "" })?;
+                }
+                write!(out, "{}}}", "  ".repeat(indent))
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut dyn Write, s: &str) -> io::Result<()> {
+    write!(out, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(out, "\\\"")?,
+            '\\' => write!(out, "\\\\")?,
+            '\n' => write!(out, "\\n")?,
+            '\r' => write!(out, "\\r")?,
+            '\t' => write!(out, "\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => write!(out, "{}", c)?,
+        }
+    }
+    write!(out, "\"")
+}
+
+/// Collapse the line breaks and indentation of XML descriptions.
+fn description(text: &str) -> Json {
+    Json::string(text.split_whitespace().collect::<Vec<&str>>().join(" "))
+}
+
+/// Value range of the MAVLink integer types.
+fn int_bounds(mavtype: &MavType) -> Option<(String, String)> {
+    use MavType::*;
+    let (min, max) = match mavtype {
+        UInt8MavlinkVersion | UInt8 => (0, u8::MAX as i128),
+        UInt16 => (0, u16::MAX as i128),
+        UInt32 => (0, u32::MAX as i128),
+        UInt64 => (0, u64::MAX as i128),
+        Int8 => (i8::MIN as i128, i8::MAX as i128),
+        Int16 => (i16::MIN as i128, i16::MAX as i128),
+        Int32 => (i32::MIN as i128, i32::MAX as i128),
+        Int64 => (i64::MIN as i128, i64::MAX as i128),
+        _ => return None,
+    };
+    Some((min.to_string(), max.to_string()))
+}
+
+fn scalar_schema(mavtype: &MavType) -> Vec<(&'static str, Json)> {
+    match int_bounds(mavtype) {
+        Some((min, max)) => vec![
+            ("type", Json::string("integer")),
+            ("minimum", Json::Number(min)),
+            ("maximum", Json::Number(max)),
+        ],
+        None => vec![("type", Json::string("number"))],
+    }
+}
+
+impl MavEnum {
+    /// The values as written to the proto file: entries without a value
+    /// are numbered after the largest one, values >= 2^31 are negative and
+    /// there is an `_UNDEFINED` zero if the enum has none.
+    fn schema(&self) -> Json {
+        let mut sorted = self.entries.iter().collect::<Vec<&MavEnumEntry>>();
+        sorted.sort_by_key(|entry| (entry.value.is_none(), entry.value));
+        let max_val = sorted.iter().filter_map(|entry| entry.value).max();
+        let mut values = vec![];
+        if max_val.unwrap_or(0) != 0 && !sorted.iter().any(|e| e.value == Some(0)) {
+            values.push(Json::object(vec![
+                ("const", Json::number(0)),
+                (
+                    "title",
+                    Json::string(format!("{}_UNDEFINED", self.raw_name)),
+                ),
+            ]));
+        }
+        for (i, entry) in sorted.iter().enumerate() {
+            // one schema per value, oneOf has to match exactly one
+            if self.alias_of(entry).is_some() {
+                continue;
+            }
+            let value = entry.value.unwrap_or(max_val.unwrap_or(0) + i as u32);
+            let mut schema = vec![
+                ("const", Json::number(value as i32)),
+                ("title", Json::string(entry.raw_name.clone())),
+            ];
+            if let Some(text) = &entry.description {
+                schema.push(("description", description(text)));
+            }
+            values.push(Json::object(schema));
+        }
+
+        let mut schema = vec![("type", Json::string("integer"))];
+        if let Some(text) = &self.description {
+            schema.push(("description", description(text)));
+        }
+        if self.bitfield.is_some() {
+            schema.push(("x-mavlink-bitmask", Json::Bool(true)));
+        }
+        schema.push(("oneOf", Json::Array(values)));
+        Json::object(schema)
+    }
+}
+
+impl MavField {
+    fn schema(&self, enums: &[&MavEnum]) -> Json {
+        let enum_ref = self.raw_enumtype.as_ref().filter(|raw| {
+            // bitmask fields hold the OR of the entries, not one of them
+            enums
+                .iter()
+                .any(|e| e.raw_name == **raw && e.bitfield.is_none())
+        });
+        let mut schema = vec![];
+        if let Some(text) = &self.description {
+            schema.push(("description", description(text)));
+        }
+        let element = match enum_ref {
+            Some(raw) => vec![("$ref", Json::string(format!("#/definitions/{}", raw)))],
+            None => match &self.mavtype {
+                MavType::Array(t, _) => scalar_schema(t),
+                t => scalar_schema(t),
+            },
+        };
+        match &self.mavtype {
+            MavType::Array(t, size) if **t == MavType::Char => {
+                schema.push(("type", Json::string("string")));
+                schema.push(("maxLength", Json::number(size)));
+            }
+            MavType::Array(_, size) => {
+                schema.push(("type", Json::string("array")));
+                schema.push(("items", Json::object(element)));
+                schema.push(("minItems", Json::number(size)));
+                schema.push(("maxItems", Json::number(size)));
+            }
+            // $ref siblings are ignored, keep the description next to it
+            _ if enum_ref.is_some() => {
+                schema.push(("allOf", Json::Array(vec![Json::object(element)])))
+            }
+            _ => schema.extend(element),
+        }
+        schema.push(("x-mavlink-type", Json::string(self.mavtype.mav_type())));
+        if let Some(units) = &self.units {
+            schema.push(("x-mavlink-units", Json::string(units.clone())));
+        }
+        if let Some(raw) = &self.raw_enumtype {
+            schema.push(("x-mavlink-enum", Json::string(raw.clone())));
+        }
+        if self.is_extension {
+            schema.push(("x-mavlink-extension", Json::Bool(true)));
+        }
+        Json::object(schema)
+    }
+}
+
+impl MavMessage {
+    fn schema(&self, enums: &[&MavEnum]) -> Json {
+        let mut schema = vec![("type", Json::string("object"))];
+        if let Some(text) = &self.description {
+            schema.push(("description", description(text)));
+        }
+        let properties = self
+            .fields
+            .iter()
+            .map(|field| {
+                (
+                    field.name.trim_start_matches("r#").to_string(),
+                    field.schema(enums),
+                )
+            })
+            .collect::<Vec<(String, Json)>>();
+        let required = properties
+            .iter()
+            .map(|(name, _)| Json::string(name.clone()))
+            .collect();
+        schema.push(("properties", Json::Object(properties)));
+        schema.push(("required", Json::Array(required)));
+        schema.push(("additionalProperties", Json::Bool(false)));
+        schema.push(("x-mavlink-id", Json::number(self.id)));
+        Json::object(schema)
+    }
+}
+
+impl MavProfile {
+    /// `<dialect>.schema.json`: every message and enum usable with this
+    /// dialect (its includes too) under `definitions`, keyed by MAVLink name.
+    pub fn emit_schema(
+        &self,
+        outf: &mut dyn Write,
+        module_name: &str,
+        modules: &HashMap<String, MavProfile>,
+    ) -> io::Result<()> {
+        let profiles = std::iter::once(self)
+            .chain(self.all_includes(modules).iter().map(|inc| {
+                modules
+                    .get(inc)
+                    .unwrap_or_else(|| panic!("Module {} not loaded!", inc))
+            }))
+            .collect::<Vec<&MavProfile>>();
+
+        // a dialect's own copy of an extended enum comes first
+        let mut enums: Vec<&MavEnum> = vec![];
+        for e in profiles.iter().flat_map(|profile| &profile.enums) {
+            if !enums.iter().any(|known| known.raw_name == e.raw_name) {
+                enums.push(e);
+            }
+        }
+        let mut messages: Vec<&MavMessage> = vec![];
+        for msg in profiles.iter().flat_map(|profile| &profile.messages) {
+            if !messages.iter().any(|known| known.id == msg.id) {
+                messages.push(msg);
+            }
+        }
+
+        let mut definitions = vec![];
+        for msg in &messages {
+            definitions.push((msg.raw_name.clone(), msg.schema(&enums)));
+        }
+        for e in &enums {
+            definitions.push((e.raw_name.clone(), e.schema()));
+        }
+        let any_message = messages
+            .iter()
+            .map(|msg| {
+                Json::object(vec![(
+                    "$ref",
+                    Json::string(format!("#/definitions/{}", msg.raw_name)),
+                )])
+            })
+            .collect();
+
+        let schema = Json::object(vec![
+            (
+                "$schema",
+                Json::string("http://json-schema.org/draft-07/schema#"),
+            ),
+            ("$id", Json::string(format!("{}.schema.json", module_name))),
+            (
+                "title",
+                Json::string(format!("{} MAVLink messages", module_name)),
+            ),
+            ("oneOf", Json::Array(any_message)),
+            ("definitions", Json::Object(definitions)),
+        ]);
+        schema.write(outf, 0)?;
+        writeln!(outf)
+    }
+}
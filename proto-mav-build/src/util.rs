@@ -0,0 +1,41 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Ident, Span};
+
+pub fn to_module_name<P: Into<PathBuf>>(file_name: P) -> String {
+    file_name
+        .into()
+        .file_stem() // remove extension
+        .unwrap()
+        .to_string_lossy() // convert to string
+        .to_lowercase() // all lowercase
+        .replace(|c: char| !c.is_alphanumeric(), "_") // remove non alphanum
+}
+
+/// Identifier for a generated name, names starting with `r#` (fields named
+/// after Rust keywords) become raw identifiers.
+pub fn ident(name: &str) -> Ident {
+    match name.strip_prefix("r#") {
+        Some(raw) => Ident::new_raw(raw, Span::call_site()),
+        None => Ident::new(name, Span::call_site()),
+    }
+}
+
+/// Write generated code to `path`, formatted with prettyplease.  Code that
+/// does not parse is written as is, to have something to debug.
+pub fn write_generated(path: &Path, code: &[u8]) {
+    let code = String::from_utf8_lossy(code);
+    let file = syn::parse_file(&code).unwrap_or_else(|error| {
+        fs::write(path, code.as_bytes()).unwrap();
+        panic!("generated {} does not parse: {}", path.display(), error)
+    });
+    fs::write(
+        path,
+        format!(
+            "// This file was automatically generated, do not edit\n{}",
+            prettyplease::unparse(&file)
+        ),
+    )
+    .unwrap();
+}
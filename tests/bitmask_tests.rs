@@ -0,0 +1,64 @@
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_bitmask {
+    use proto_mav::proto::common::MavSysStatusSensor;
+
+    #[test]
+    pub fn test_iter_set_bits() {
+        let value = MavSysStatusSensor::MavSysStatusSensor3dGyro.bit()
+            | MavSysStatusSensor::MavSysStatusSensor3dMag.bit();
+        let set: Vec<MavSysStatusSensor> = MavSysStatusSensor::iter_set_bits(value).collect();
+        assert_eq!(
+            set,
+            vec![
+                MavSysStatusSensor::MavSysStatusSensor3dGyro,
+                MavSysStatusSensor::MavSysStatusSensor3dMag
+            ]
+        );
+        assert_eq!(MavSysStatusSensor::unknown_bits(value), 0);
+    }
+
+    #[test]
+    pub fn test_is_set() {
+        let value = MavSysStatusSensor::MavSysStatusSensor3dAccel.bit();
+        assert!(MavSysStatusSensor::MavSysStatusSensor3dAccel.is_set(value));
+        assert!(!MavSysStatusSensor::MavSysStatusSensor3dGyro.is_set(value));
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "autoquad")]
+mod test_top_bit {
+    use proto_mav::proto::autoquad::AutoquadNavStatus;
+
+    #[test]
+    pub fn test_top_bit_value() {
+        // int32 in the proto enum, same bits as the MAVLink value
+        assert_eq!(
+            AutoquadNavStatus::AqNavStatusFailsafe as i32 as u32,
+            0x8000_0000
+        );
+        assert_eq!(
+            AutoquadNavStatus::from_i32(0x8000_0000u32 as i32),
+            Some(AutoquadNavStatus::AqNavStatusFailsafe)
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "localtest")]
+mod test_combined_entries {
+    use proto_mav::proto::localtest::LocaltestFlags;
+
+    #[test]
+    pub fn test_masks_are_not_bits() {
+        assert_eq!(
+            LocaltestFlags::ALL_BITS,
+            &[LocaltestFlags::Left, LocaltestFlags::Right]
+        );
+        let value = LocaltestFlags::Both.bit();
+        let set: Vec<LocaltestFlags> = LocaltestFlags::iter_set_bits(value).collect();
+        assert_eq!(set, vec![LocaltestFlags::Left, LocaltestFlags::Right]);
+        assert_eq!(LocaltestFlags::unknown_bits(value | 4), 4);
+    }
+}
@@ -0,0 +1,38 @@
+mod test_shared;
+
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_builders {
+    use proto_mav::proto::common::*;
+
+    #[test]
+    pub fn test_builder_matches_literal() {
+        let msg = CommandInt::builder()
+            .param1(1.0)
+            .param2(2.0)
+            .param3(3.0)
+            .param4(4.0)
+            .x(555)
+            .y(666)
+            .z(777.0)
+            .command(MavCmd::NavTakeoff)
+            .target(42, 84)
+            .frame(MavFrame::Global)
+            .current(73)
+            .autocontinue(17)
+            .build();
+        assert_eq!(msg, crate::test_shared::get_cmd_nav_takeoff_msg());
+        assert_eq!(msg.command(), MavCmd::NavTakeoff);
+    }
+
+    #[test]
+    pub fn test_builder_strings_and_defaults() {
+        let msg = Statustext::builder()
+            .severity(MavSeverity::Warning)
+            .text("low battery")
+            .build();
+        assert_eq!(msg.severity(), MavSeverity::Warning);
+        assert_eq!(msg.text, "low battery");
+        assert_eq!(Heartbeat::builder().build(), Heartbeat::default());
+    }
+}
@@ -0,0 +1,55 @@
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_commands {
+    use proto_mav::mavlink::common::cmd;
+    use proto_mav::proto::common::{CommandLong, MavCmd, MavFrame};
+    use std::convert::TryFrom;
+
+    fn takeoff() -> cmd::NavTakeoff {
+        cmd::NavTakeoff {
+            pitch: 15.0,
+            yaw: 90.0,
+            latitude: 47.397_742_1,
+            longitude: 8.545_593_9,
+            altitude: 25.0,
+        }
+    }
+
+    #[test]
+    pub fn test_command_long() {
+        let msg = takeoff().into_command_long(1, 1);
+        assert_eq!(msg.target_system, 1);
+        assert_eq!(msg.command, MavCmd::NavTakeoff as i32);
+        assert_eq!(msg.param1, 15.0);
+        assert_eq!(msg.param2, 0.0);
+        assert_eq!(msg.param4, 90.0);
+        assert_eq!(msg.param7, 25.0);
+
+        let back = cmd::NavTakeoff::try_from(&msg).unwrap();
+        assert_eq!(back.pitch, 15.0);
+        assert_eq!(back.latitude, f64::from(msg.param5));
+    }
+
+    #[test]
+    pub fn test_command_int() {
+        let msg = takeoff().into_command_int(1, 1, MavFrame::GlobalRelativeAltInt);
+        assert_eq!(msg.x, 473_977_421);
+        assert_eq!(msg.y, 85_455_939);
+        assert_eq!(msg.z, 25.0);
+
+        let back = cmd::NavTakeoff::try_from(&msg).unwrap();
+        assert!((back.latitude - 47.397_742_1).abs() < 1e-7);
+        assert_eq!(back.yaw, 90.0);
+    }
+
+    #[test]
+    pub fn test_other_command() {
+        let msg = CommandLong {
+            command: MavCmd::NavLand as i32,
+            ..Default::default()
+        };
+        let err = cmd::NavTakeoff::try_from(&msg).unwrap_err();
+        assert_eq!(err.expected, MavCmd::NavTakeoff as i32 as u32);
+        assert_eq!(err.found, MavCmd::NavLand as i32 as u32);
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+#[cfg(all(feature = "common", feature = "ardupilotmega"))]
+mod test_dialect_spec {
+    use proto_mav::*;
+
+    pub const HEARTBEAT_PAYLOAD: &'static [u8] =
+        &[0x05, 0x00, 0x00, 0x00, 0x02, 0x03, 0x59, 0x03, 0x03];
+
+    #[test]
+    pub fn test_message_info_through_includes() {
+        let dialects: Vec<&dyn DialectSpec> =
+            vec![&mavlink::common::Dialect, &mavlink::ardupilotmega::Dialect];
+        for dialect in dialects {
+            let info = dialect.message_info(0).expect("HEARTBEAT not found");
+            assert_eq!(info.name, "Heartbeat");
+            assert_eq!(info.encoded_len, 9);
+            assert_eq!(dialect.extra_crc(0), Some(50));
+        }
+        assert!(mavlink::common::Dialect.message_info(150).is_none());
+        assert!(mavlink::ardupilotmega::Dialect.message_info(150).is_some());
+    }
+
+    #[test]
+    pub fn test_field_info() {
+        let info = mavlink::common::Dialect.message_info(24).unwrap();
+        assert_eq!(info, proto::common::GpsRawInt::INFO);
+        let names = info.fields.iter().map(|f| f.name).collect::<Vec<_>>();
+        // wire order, largest types first
+        assert_eq!(&names[..3], &["time_usec", "lat", "lon"]);
+        let fix_type = info.fields.iter().find(|f| f.name == "fix_type").unwrap();
+        assert_eq!(fix_type.mav_type, "uint8_t");
+        assert_eq!(fix_type.enum_type, Some("GPS_FIX_TYPE"));
+        let alt = info.fields.iter().find(|f| f.name == "alt").unwrap();
+        assert_eq!(alt.units, Some("mm"));
+        assert!(!alt.is_extension);
+        // from the XML, with or without the extension fields generated
+        assert!(info.has_extensions);
+    }
+
+    #[test]
+    pub fn test_descriptor_table() {
+        let desc = mavlink::ardupilotmega::MavMessage::descriptor(0).unwrap();
+        assert_eq!(desc.name, "Heartbeat");
+        assert_eq!(desc.extra_crc, 50);
+        assert_eq!(desc.min_encoded_len, 9);
+        assert!(!desc.has_extensions);
+        assert!(mavlink::common::MavMessage::descriptor(150).is_none());
+
+        let messages = mavlink::ardupilotmega::Dialect.messages();
+        assert!(messages.iter().any(|m| m.id == 0));
+        assert!(messages.iter().any(|m| m.id == 150));
+        for message in mavlink::common::Dialect.messages() {
+            assert_eq!(
+                mavlink::common::MavMessage::descriptor(message.id),
+                Some(*message)
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_dialect_info() {
+        assert_eq!(mavlink::common::MAVLINK_VERSION, "3");
+        assert_eq!(mavlink::common::DIALECT_NUMBER, 0);
+        // ardupilotmega only has <dialect>, the version comes from common
+        let info = mavlink::ardupilotmega::Dialect.info();
+        assert_eq!(
+            info,
+            DialectInfo {
+                name: "ardupilotmega",
+                mavlink_version: "3",
+                dialect_number: 2,
+            }
+        );
+        assert_eq!(info, mavlink::ardupilotmega::DIALECT_INFO);
+    }
+
+    #[test]
+    pub fn test_command_info() {
+        let dialect: &dyn DialectSpec = &mavlink::ardupilotmega::Dialect;
+        let takeoff = dialect
+            .command_info(22)
+            .expect("MAV_CMD_NAV_TAKEOFF not found");
+        assert_eq!(takeoff.name, "NavTakeoff");
+        let pitch = &takeoff.params[0];
+        assert_eq!(pitch.index, 1);
+        assert_eq!(pitch.label, Some("Pitch"));
+        assert_eq!(pitch.units, Some("deg"));
+        assert!(!pitch.reserved);
+        let altitude = takeoff.params.iter().find(|p| p.index == 7).unwrap();
+        assert_eq!(altitude.units, Some("m"));
+
+        // MAV_CMD_DO_SET_MODE: an enum param
+        let set_mode = dialect.command_info(176).unwrap();
+        assert_eq!(set_mode.params[0].enum_type, Some("MAV_MODE"));
+        assert!(dialect.commands().iter().any(|c| c.name == "DoSetMode"));
+    }
+
+    #[test]
+    pub fn test_dynamic_parse() {
+        let dialect: &dyn DialectSpec = &mavlink::ardupilotmega::Dialect;
+        let msg = dialect
+            .parse(MavlinkVersion::V2, 0, HEARTBEAT_PAYLOAD)
+            .expect("Failed to parse HEARTBEAT");
+        assert_eq!(msg.message_id(), 0);
+        assert_eq!(msg.mavlink_ser(), HEARTBEAT_PAYLOAD);
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "standard")]
+mod test_included_commands {
+    use proto_mav::*;
+
+    #[test]
+    pub fn test_commands_of_includes() {
+        // standard declares no MAV_CMD, the commands come from common
+        let dialect: &dyn DialectSpec = &mavlink::standard::Dialect;
+        let takeoff = dialect
+            .command_info(22)
+            .expect("MAV_CMD_NAV_TAKEOFF not found");
+        assert_eq!(takeoff.name, "NavTakeoff");
+        assert_eq!(
+            dialect.commands().len(),
+            mavlink::common::Dialect.commands().len()
+        );
+    }
+}
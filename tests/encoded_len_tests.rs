@@ -0,0 +1,152 @@
+mod test_shared;
+
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_encoded_len {
+    use proto_mav::*;
+
+    /// (min, max) payload lengths from the upstream MAVLink C headers
+    /// (MAVLINK_MSG_ID_*_MIN_LEN / MAVLINK_MSG_ID_*_LEN).
+    const REFERENCE_LENS: &[(&str, usize, usize, usize, usize)] = &[
+        (
+            "HEARTBEAT",
+            proto::common::Heartbeat::MIN_ENCODED_LEN,
+            proto::common::Heartbeat::ENCODED_LEN,
+            9,
+            9,
+        ),
+        (
+            "SYS_STATUS",
+            proto::common::SysStatus::MIN_ENCODED_LEN,
+            proto::common::SysStatus::ENCODED_LEN,
+            31,
+            31,
+        ),
+        (
+            "PARAM_VALUE",
+            proto::common::ParamValue::MIN_ENCODED_LEN,
+            proto::common::ParamValue::ENCODED_LEN,
+            25,
+            25,
+        ),
+        (
+            "GPS_RAW_INT",
+            proto::common::GpsRawInt::MIN_ENCODED_LEN,
+            proto::common::GpsRawInt::ENCODED_LEN,
+            30,
+            52,
+        ),
+        (
+            "ATTITUDE",
+            proto::common::Attitude::MIN_ENCODED_LEN,
+            proto::common::Attitude::ENCODED_LEN,
+            28,
+            28,
+        ),
+        (
+            "SERVO_OUTPUT_RAW",
+            proto::common::ServoOutputRaw::MIN_ENCODED_LEN,
+            proto::common::ServoOutputRaw::ENCODED_LEN,
+            21,
+            37,
+        ),
+        (
+            "MISSION_ITEM_INT",
+            proto::common::MissionItemInt::MIN_ENCODED_LEN,
+            proto::common::MissionItemInt::ENCODED_LEN,
+            37,
+            38,
+        ),
+        (
+            "COMMAND_INT",
+            proto::common::CommandInt::MIN_ENCODED_LEN,
+            proto::common::CommandInt::ENCODED_LEN,
+            35,
+            35,
+        ),
+        (
+            "COMMAND_LONG",
+            proto::common::CommandLong::MIN_ENCODED_LEN,
+            proto::common::CommandLong::ENCODED_LEN,
+            33,
+            33,
+        ),
+        (
+            "STATUSTEXT",
+            proto::common::Statustext::MIN_ENCODED_LEN,
+            proto::common::Statustext::ENCODED_LEN,
+            51,
+            54,
+        ),
+    ];
+
+    #[test]
+    pub fn test_reference_lengths() {
+        for (name, min_len, len, ref_min, ref_max) in REFERENCE_LENS {
+            assert_eq!(min_len, ref_min, "{} min length", name);
+            // extension fields are only generated with emit-extensions
+            assert!(
+                len == ref_min || len == ref_max,
+                "{} length {} is neither {} nor {}",
+                name,
+                len,
+                ref_min,
+                ref_max
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_serialized_len() {
+        let msg = crate::test_shared::get_heartbeat_msg();
+        assert_eq!(
+            msg.mavlink_ser().len(),
+            proto::common::Heartbeat::ENCODED_LEN
+        );
+        let msg = crate::test_shared::get_cmd_nav_takeoff_msg();
+        assert_eq!(
+            msg.mavlink_ser().len(),
+            proto::common::CommandInt::ENCODED_LEN
+        );
+    }
+
+    /// Wire layout of HEARTBEAT: largest types first, in XML order.
+    #[test]
+    pub fn test_heartbeat_field_order() {
+        let msg = crate::test_shared::get_heartbeat_msg();
+        assert_eq!(msg.mavlink_ser(), vec![5, 0, 0, 0, 2, 3, 89, 3, 3]);
+    }
+
+    #[test]
+    pub fn test_ser_into_stack_buffer() {
+        let msg = crate::test_shared::get_heartbeat_msg();
+        let mut buf = [0xff; 16];
+        assert_eq!(msg.mavlink_ser_into(&mut buf), Ok(9));
+        assert_eq!(&buf[..9], &msg.mavlink_ser()[..]);
+        assert_eq!(buf[9], 0xff);
+
+        let msg = mavlink::common::MavMessage::Heartbeat(msg);
+        assert_eq!(msg.mavlink_ser_into(&mut buf), Ok(9));
+        assert_eq!(
+            msg.mavlink_ser_into(&mut buf[..8]),
+            Err(BufferError { len: 8, needed: 9 })
+        );
+    }
+
+    /// Arrays are written in full whatever the length of their Vec.
+    #[test]
+    pub fn test_ser_short_array() {
+        let mut msg = proto::common::HilActuatorControls::default();
+        msg.controls = vec![1.0];
+        let payload = msg.mavlink_ser();
+        assert_eq!(
+            payload.len(),
+            proto::common::HilActuatorControls::ENCODED_LEN
+        );
+        let decoded =
+            proto::common::HilActuatorControls::mavlink_deser(MavlinkVersion::V2, &payload)
+                .unwrap();
+        assert_eq!(decoded.controls.len(), 16);
+        assert_eq!(decoded.controls[0], 1.0);
+    }
+}
@@ -0,0 +1,28 @@
+#[cfg(test)]
+#[cfg(feature = "ardupilotmega")]
+mod test_enum_bridges {
+    use proto_mav::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    pub fn test_included_enum_into_dialect_enum() {
+        let cmd: proto::ardupilotmega::MavCmd = proto::common::MavCmd::NavWaypoint.into();
+        assert_eq!(cmd, proto::ardupilotmega::MavCmd::NavWaypoint);
+    }
+
+    #[test]
+    pub fn test_dialect_enum_into_included_enum() {
+        let cmd = proto::common::MavCmd::try_from(proto::ardupilotmega::MavCmd::NavWaypoint);
+        assert_eq!(cmd.unwrap(), proto::common::MavCmd::NavWaypoint);
+        // ardupilotmega only
+        assert!(
+            proto::common::MavCmd::try_from(proto::ardupilotmega::MavCmd::DoSendBanner).is_err()
+        );
+    }
+
+    #[test]
+    pub fn test_enums_reexport() {
+        let _: mavlink::ardupilotmega::enums::MavFrame = proto::common::MavFrame::Global;
+        let _: mavlink::ardupilotmega::enums::MavCmd = proto::ardupilotmega::MavCmd::DoSendBanner;
+    }
+}
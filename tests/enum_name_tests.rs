@@ -0,0 +1,97 @@
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_enum_names {
+    use proto_mav::proto::common::*;
+
+    #[test]
+    pub fn test_mav_name() {
+        assert_eq!(MavState::Active.mav_name(), "MAV_STATE_ACTIVE");
+        assert_eq!(MavCmd::NavWaypoint.mav_name(), "MAV_CMD_NAV_WAYPOINT");
+        // added by the proto file, MAV_CMD has no 0 entry
+        assert_eq!(MavCmd::Undefined.mav_name(), "MAV_CMD_UNDEFINED");
+        assert_eq!(MavState::Active.to_string(), "MAV_STATE_ACTIVE");
+    }
+
+    #[test]
+    pub fn test_from_mav_name() {
+        assert_eq!(
+            MavCmd::from_mav_name("MAV_CMD_NAV_WAYPOINT"),
+            Some(MavCmd::NavWaypoint)
+        );
+        assert_eq!(
+            MavState::from_mav_name("MAV_STATE_ACTIVE"),
+            Some(MavState::Active)
+        );
+        assert_eq!(MavState::from_mav_name("Active"), None);
+    }
+
+    #[test]
+    pub fn test_from_str() {
+        assert_eq!("MAV_STATE_ACTIVE".parse(), Ok(MavState::Active));
+        let err = "MAV_STATE_NONE".parse::<MavState>().unwrap_err();
+        assert_eq!(err.enum_type, "MAV_STATE");
+        assert_eq!(err.name, "MAV_STATE_NONE");
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "localtest")]
+mod test_enum_aliases {
+    use proto_mav::proto::localtest::*;
+    use proto_mav::*;
+
+    #[test]
+    pub fn test_allow_alias() {
+        let sources = proto_mav::proto_sources();
+        let (_, localtest) = sources
+            .iter()
+            .find(|(name, _)| *name == "localtest.proto")
+            .expect("localtest.proto missing");
+        let mode = localtest
+            .split("enum LOCALTEST_MODE {")
+            .nth(1)
+            .expect("LOCALTEST_MODE missing");
+        let mode = &mode[..mode.find('}').unwrap()];
+        assert!(mode.contains("option allow_alias = true;"));
+        assert!(mode.contains("LOCALTEST_MODE_ENABLED = 1;"));
+    }
+
+    #[test]
+    pub fn test_alias_const() {
+        assert_eq!(LocaltestMode::Enabled, LocaltestMode::On);
+        assert_eq!(LocaltestMode::Enabled.mav_name(), "LOCALTEST_MODE_ON");
+        assert_eq!(
+            LocaltestMode::from_mav_name("LOCALTEST_MODE_ENABLED"),
+            Some(LocaltestMode::On)
+        );
+    }
+
+    #[test]
+    pub fn test_merged_include_alias() {
+        // MAV_TYPE_QUADROTOR comes from common and shares its value with
+        // MAV_TYPE_SAUCER, so it is kept as an alias
+        assert_eq!(MavType::Quadrotor, MavType::Saucer);
+        assert_eq!(MavType::Saucer as i32, 2);
+        assert_eq!(
+            MavType::from_mav_name("MAV_TYPE_QUADROTOR"),
+            Some(MavType::Saucer)
+        );
+        assert_eq!(MavType::from_mav_name("MAV_TYPE_UFO"), Some(MavType::Ufo));
+    }
+
+    #[test]
+    pub fn test_command_alias() {
+        assert_eq!(MavCmd::LocaltestGrip, MavCmd::LocaltestGrab);
+        let commands = mavlink::localtest::Dialect.commands();
+        let grab = commands
+            .iter()
+            .filter(|c| c.id == 55211)
+            .map(|c| c.name)
+            .collect::<Vec<_>>();
+        assert_eq!(grab, ["LocaltestGrab"]);
+        assert_eq!(
+            mavlink::localtest::cmd::LocaltestGrab::COMMAND,
+            MavCmd::LocaltestGrip
+        );
+    }
+}
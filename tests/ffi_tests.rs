@@ -0,0 +1,117 @@
+#[cfg(test)]
+#[cfg(all(feature = "ffi", feature = "common"))]
+mod test_ffi {
+    use proto_mav::ffi::*;
+
+    /// HEARTBEAT, sequence 239 from system 1, component 1.
+    const HEARTBEAT_V2: &[u8] = &[
+        0xfd, 0x09, 0, 0, 0xef, 0x01, 0x01, 0x00, 0x00, 0x00, //header
+        0x05, 0x00, 0x00, 0x00, 0x02, 0x03, 0x59, 0x03, 0x03, //payload
+        16, 240, //checksum
+    ];
+
+    fn empty_frame() -> ProtoMavFrame {
+        ProtoMavFrame {
+            version: 0,
+            sequence: 0,
+            system_id: 0,
+            component_id: 0,
+            message_id: 0,
+            payload_len: 0,
+            payload: [0; 255],
+        }
+    }
+
+    #[test]
+    pub fn test_parse_serialize() {
+        let mut frame = empty_frame();
+        let len = unsafe { proto_mav_parse(HEARTBEAT_V2.as_ptr(), HEARTBEAT_V2.len(), &mut frame) };
+        assert_eq!(len, HEARTBEAT_V2.len() as i32);
+        assert_eq!(frame.version, 2);
+        assert_eq!(frame.sequence, 239);
+        assert_eq!(frame.message_id, 0);
+        assert_eq!(
+            &frame.payload[..frame.payload_len.into()],
+            &HEARTBEAT_V2[10..19]
+        );
+
+        let mut buf = [0; 280];
+        let len = unsafe { proto_mav_serialize(&frame, buf.as_mut_ptr(), buf.len()) };
+        assert_eq!(&buf[..len as usize], HEARTBEAT_V2);
+        let len = unsafe { proto_mav_serialize(&frame, buf.as_mut_ptr(), 8) };
+        assert_eq!(len, PROTO_MAV_ERR_BUFFER_TOO_SMALL);
+    }
+
+    #[test]
+    pub fn test_parse_errors() {
+        let mut frame = empty_frame();
+        let mut corrupt = HEARTBEAT_V2.to_vec();
+        corrupt[12] ^= 1;
+        let parse = |buf: &[u8], frame: &mut ProtoMavFrame| unsafe {
+            proto_mav_parse(buf.as_ptr(), buf.len(), frame)
+        };
+        assert_eq!(parse(&corrupt, &mut frame), PROTO_MAV_ERR_CRC);
+        assert_eq!(
+            parse(&HEARTBEAT_V2[..15], &mut frame),
+            PROTO_MAV_ERR_INCOMPLETE
+        );
+        assert_eq!(parse(&[0x55, 0], &mut frame), PROTO_MAV_ERR_MAGIC);
+    }
+
+    #[test]
+    pub fn test_convert() {
+        use proto_mav::DialectSpec;
+
+        let mut frame = empty_frame();
+        unsafe { proto_mav_parse(HEARTBEAT_V2.as_ptr(), HEARTBEAT_V2.len(), &mut frame) };
+        assert_eq!(unsafe { proto_mav_convert(&mut frame, 1) }, 0);
+        assert_eq!(frame.version, 1);
+        let mut buf = [0; 280];
+        let len = unsafe { proto_mav_serialize(&frame, buf.as_mut_ptr(), buf.len()) };
+        assert_eq!(&buf[..6], &[0xfe, 9, 239, 1, 1, 0]);
+        assert_eq!(&buf[6..15], &HEARTBEAT_V2[10..19]);
+
+        let mut v1 = empty_frame();
+        assert_eq!(
+            unsafe { proto_mav_parse(buf.as_ptr(), len as usize, &mut v1) },
+            len
+        );
+        assert_eq!(unsafe { proto_mav_convert(&mut v1, 2) }, 0);
+        let len = unsafe { proto_mav_serialize(&v1, buf.as_mut_ptr(), buf.len()) };
+        assert_eq!(&buf[..len as usize], HEARTBEAT_V2);
+
+        // SYS_STATUS with a set extension field
+        let info = proto_mav::mavlink::common::Dialect.message_info(1).unwrap();
+        let mut frame = empty_frame();
+        frame.version = 2;
+        frame.message_id = 1;
+        frame.payload_len = info.min_encoded_len as u8 + 1;
+        frame.payload[info.min_encoded_len] = 1;
+        assert_eq!(unsafe { proto_mav_convert(&mut frame, 1) }, PROTO_MAV_LOSSY);
+        assert_eq!(usize::from(frame.payload_len), info.min_encoded_len);
+        assert!(frame.payload.iter().all(|&b| b == 0));
+
+        frame.message_id = 0x100;
+        frame.version = 2;
+        assert_eq!(
+            unsafe { proto_mav_convert(&mut frame, 1) },
+            PROTO_MAV_ERR_INVALID
+        );
+    }
+
+    #[test]
+    pub fn test_message_names() {
+        let mut buf = [0 as std::os::raw::c_char; 32];
+        let len = unsafe { proto_mav_message_name(1, buf.as_mut_ptr(), buf.len()) };
+        let name = unsafe { std::ffi::CStr::from_ptr(buf.as_ptr()) };
+        assert_eq!(name.to_str(), Ok("SysStatus"));
+        assert_eq!(len, 9);
+
+        for name in &["SysStatus\0", "SYS_STATUS\0"] {
+            let id = unsafe { proto_mav_message_id(name.as_ptr() as *const _) };
+            assert_eq!(id, 1);
+        }
+        let id = unsafe { proto_mav_message_id("NO_SUCH_MESSAGE\0".as_ptr() as *const _) };
+        assert_eq!(id, PROTO_MAV_ERR_UNKNOWN_MESSAGE);
+    }
+}
@@ -0,0 +1,20 @@
+#[cfg(test)]
+#[cfg(all(feature = "grpc", feature = "common"))]
+mod test_grpc {
+    use proto_mav::*;
+
+    #[test]
+    pub fn test_frame_round_trip() {
+        let msg = mavlink::common::MavMessage::Heartbeat(proto::common::Heartbeat {
+            custom_mode: 5,
+            ..Default::default()
+        });
+        let frame = proto::mav_stream::MavFrame::new("common", 1, 2, &msg);
+        assert_eq!(frame.dialect, "common");
+        assert_eq!(frame.system_id, 1);
+        assert_eq!(frame.component_id, 2);
+        assert_eq!(frame.message_id, 0);
+        let back: mavlink::common::MavMessage = frame.message().unwrap();
+        assert_eq!(back, msg);
+    }
+}
@@ -1,5 +1,5 @@
 #[cfg(test)]
-#[cfg(all(feature = "std", feature = "common"))]
+#[cfg(feature = "common")]
 mod helper_tests {
     use proto_mav::mavlink::common::MavMessage;
     use proto_mav::Message;
@@ -21,4 +21,170 @@ mod helper_tests {
             "Message name does not match"
         );
     }
+
+    #[test]
+    fn test_default_message_from_name() {
+        for name in &["HEARTBEAT", "Heartbeat"] {
+            match MavMessage::default_message_from_name(name) {
+                Ok(MavMessage::Heartbeat(_)) => {}
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert!(MavMessage::default_message_from_name("NO_SUCH_MESSAGE").is_err());
+    }
+
+    #[test]
+    fn test_message_kind() {
+        use proto_mav::mavlink::common::MavMessageKind;
+        use std::convert::TryFrom;
+
+        let message = MavMessage::default_message_from_id(4).unwrap();
+        assert_eq!(message.kind(), MavMessageKind::Ping);
+        assert_eq!(message.kind().id(), 4);
+        assert_eq!(message.kind().name(), message.message_name());
+        assert_eq!(MavMessageKind::try_from(4).unwrap(), MavMessageKind::Ping);
+        assert!(MavMessageKind::try_from(u32::MAX).is_err());
+    }
+
+    #[cfg(feature = "ardupilotmega")]
+    #[test]
+    fn test_lookup_through_includes() {
+        use proto_mav::mavlink::ardupilotmega;
+
+        assert_eq!(
+            ardupilotmega::MavMessage::message_id_from_name("Ping"),
+            Ok(4)
+        );
+        assert_eq!(
+            ardupilotmega::MavMessage::message_id_from_name("SensorOffsets"),
+            Ok(150)
+        );
+        assert!(ardupilotmega::MavMessage::message_id_from_name("NoSuchMessage").is_err());
+        match ardupilotmega::MavMessage::default_message_from_id(4) {
+            Ok(ardupilotmega::MavMessage::Common(MavMessage::Ping(_))) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(ardupilotmega::MavMessage::default_message_from_id(u32::MAX).is_err());
+    }
+
+    #[cfg(feature = "ardupilotmega")]
+    #[test]
+    fn test_parse_through_includes() {
+        use proto_mav::error::ParserError;
+        use proto_mav::mavlink::ardupilotmega;
+        use proto_mav::MavlinkVersion;
+
+        let payload = [5, 0, 0, 0, 2, 3, 89, 3, 3];
+        match ardupilotmega::MavMessage::parse(MavlinkVersion::V2, 0, &payload) {
+            Ok(ardupilotmega::MavMessage::Common(MavMessage::Heartbeat(msg))) => {
+                assert_eq!(msg.custom_mode, 5)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match ardupilotmega::MavMessage::parse(MavlinkVersion::V2, u32::MAX, &payload) {
+            Err(ParserError::UnknownMessage { id: u32::MAX }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(ardupilotmega::MavMessage::extra_crc(0), 50);
+    }
+
+    #[test]
+    fn test_lookup_extra_crc() {
+        assert_eq!(MavMessage::lookup_extra_crc(0), Some(50));
+        assert_eq!(MavMessage::lookup_extra_crc(u32::MAX), None);
+        assert_eq!(MavMessage::extra_crc(u32::MAX), 0);
+        let ids = proto_mav::mavlink::common::EXTRA_CRCS
+            .iter()
+            .map(|&(id, _)| id);
+        assert!(ids.clone().zip(ids.skip(1)).all(|(a, b)| a < b));
+    }
+
+    #[test]
+    fn test_target() {
+        let mut message = MavMessage::default_message_from_name("COMMAND_LONG").unwrap();
+        if let MavMessage::CommandLong(ref mut body) = message {
+            body.target_system = 1;
+            body.target_component = 190;
+        }
+        assert_eq!(message.target(), Some((1, 190)));
+        let message = MavMessage::default_message_from_name("HEARTBEAT").unwrap();
+        assert_eq!(message.target(), None);
+    }
+
+    #[test]
+    fn test_passthrough() {
+        use proto_mav::MavlinkVersion;
+        use proto_mav::{Passthrough, PayloadEncoding};
+
+        let payload = [5, 0, 0, 0, 2, 3, 89, 3, 3];
+        let msg = Passthrough::<MavMessage>::parse(MavlinkVersion::V2, 0, &payload).unwrap();
+        assert!(matches!(msg.known(), Some(MavMessage::Heartbeat(_))));
+
+        let msg = Passthrough::<MavMessage>::parse(MavlinkVersion::V2, 60000, &payload).unwrap();
+        assert_eq!(
+            msg,
+            Passthrough::Unknown {
+                id: 60000,
+                payload: payload.to_vec(),
+                encoding: PayloadEncoding::Mavlink,
+            }
+        );
+        assert_eq!(msg.message_id(), 60000);
+        assert_eq!(msg.mavlink_ser(), payload);
+        assert!(msg.proto_encode().is_empty());
+        assert!(msg.known().is_none());
+
+        let msg = Passthrough::<MavMessage>::proto_parse(60000, &payload).unwrap();
+        assert_eq!(
+            msg,
+            Passthrough::Unknown {
+                id: 60000,
+                payload: payload.to_vec(),
+                encoding: PayloadEncoding::Proto,
+            }
+        );
+        assert_eq!(msg.proto_encode(), payload);
+        assert!(msg.mavlink_ser().is_empty());
+    }
+
+    #[test]
+    fn test_passthrough_frame() {
+        use proto_mav::{FrameError, Passthrough, PayloadEncoding};
+
+        let mut frame = vec![
+            0xfd, 0x09, 0, 0, 0xef, 0x01, 0x01, 0x00, 0x00, 0x00, //header
+            0x05, 0x00, 0x00, 0x00, 0x02, 0x03, 0x59, 0x03, 0x03, //payload
+            16, 240, //checksum
+        ];
+        let (msg, len) = Passthrough::<MavMessage>::parse_frame(&frame).unwrap();
+        assert_eq!(len, frame.len());
+        assert!(matches!(msg.known(), Some(MavMessage::Heartbeat(_))));
+        assert!(msg.frame().is_none());
+
+        frame[19] ^= 0xff;
+        assert!(matches!(
+            Passthrough::<MavMessage>::parse_frame(&frame),
+            Err(FrameError::Crc)
+        ));
+
+        // id 60000, the checksum can not be checked and is kept as it is
+        frame[7..10].copy_from_slice(&[0x60, 0xea, 0x00]);
+        frame.extend_from_slice(&[0xfd, 0x00]); // the start of the next frame
+        let (msg, len) = Passthrough::<MavMessage>::parse_frame(&frame).unwrap();
+        assert_eq!(len, 21);
+        assert_eq!(msg.message_id(), 60000);
+        assert_eq!(msg.frame(), Some(&frame[..21]));
+        assert_eq!(msg.mavlink_ser(), &frame[10..19]);
+        assert!(matches!(
+            msg,
+            Passthrough::Unknown {
+                encoding: PayloadEncoding::Frame,
+                ..
+            }
+        ));
+        assert!(matches!(
+            Passthrough::<MavMessage>::parse_frame(&frame[..20]),
+            Err(FrameError::Incomplete)
+        ));
+    }
 }
@@ -0,0 +1,87 @@
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_invalid_values {
+    use proto_mav::*;
+
+    #[test]
+    pub fn test_unset_uses_sentinels() {
+        let gps = proto::common::GpsRawInt::unset();
+        assert_eq!(gps.eph, u32::from(u16::MAX));
+        assert_eq!(proto::common::GpsRawInt::EPH_INVALID, u32::from(u16::MAX));
+        assert!(!gps.eph_is_valid());
+        assert!(!gps.cog_is_valid());
+        // no sentinel in the description, stays zero
+        assert_eq!(gps.lat, 0);
+
+        let mut gps = proto::common::GpsRawInt::default();
+        assert!(gps.eph_is_valid());
+        gps.eph = u32::from(u16::MAX);
+        assert!(!gps.eph_is_valid());
+
+        assert_eq!(
+            proto::common::Heartbeat::unset(),
+            proto::common::Heartbeat::default()
+        );
+    }
+
+    #[test]
+    pub fn test_array_sentinel() {
+        let mut msg = proto::common::AttitudeQuaternionCov::unset();
+        assert_eq!(msg.covariance.len(), 9);
+        assert!(msg.covariance.iter().all(|v| v.is_nan()));
+        assert!(proto::common::AttitudeQuaternionCov::COVARIANCE_INVALID.is_nan());
+        assert!(!msg.covariance_is_valid());
+        msg.covariance[0] = 0.5;
+        assert!(msg.covariance_is_valid());
+    }
+
+    #[test]
+    pub fn test_unset_from_id() {
+        match mavlink::common::MavMessage::unset_from_id(24) {
+            Ok(mavlink::common::MavMessage::GpsRawInt(gps)) => assert!(!gps.eph_is_valid()),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(mavlink::common::MavMessage::unset_from_id(150).is_err());
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "localtest")]
+mod test_invalid_attribute {
+    use proto_mav::proto::localtest::LocaltestInvalid;
+
+    #[test]
+    pub fn test_scalar_attribute() {
+        let mut msg = LocaltestInvalid::unset();
+        assert_eq!(LocaltestInvalid::SPEED_INVALID, u32::from(u16::MAX));
+        assert_eq!(LocaltestInvalid::OFFSET_INVALID, -1);
+        assert!(!msg.speed_is_valid());
+        assert!(!msg.offset_is_valid());
+        msg.speed = 3;
+        msg.offset = 0;
+        assert!(msg.speed_is_valid());
+        assert!(msg.offset_is_valid());
+    }
+
+    #[test]
+    pub fn test_all_elements_attribute() {
+        let mut msg = LocaltestInvalid::unset();
+        assert_eq!(msg.ranges, vec![u32::from(u16::MAX); 4]);
+        assert!(!msg.ranges_is_valid());
+        // any element set makes the array valid
+        msg.ranges[3] = 10;
+        assert!(msg.ranges_is_valid());
+    }
+
+    #[test]
+    pub fn test_first_element_attribute() {
+        let mut msg = LocaltestInvalid::unset();
+        assert_eq!(msg.covariance.len(), 3);
+        assert!(!msg.covariance_is_valid());
+        // only the first element decides
+        msg.covariance[1] = 0.5;
+        assert!(!msg.covariance_is_valid());
+        msg.covariance[0] = 0.5;
+        assert!(msg.covariance_is_valid());
+    }
+}
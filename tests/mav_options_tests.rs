@@ -0,0 +1,50 @@
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_mav_options {
+    use proto_mav::proto::mav_options;
+
+    #[test]
+    pub fn test_message_options_from_descriptor_set() {
+        let options = mav_options();
+        let heartbeat = options
+            .iter()
+            .find(|message| message.full_name == "common.HEARTBEAT")
+            .expect("HEARTBEAT not in the descriptor set");
+        assert_eq!(heartbeat.id, Some(0));
+        let (name, custom_mode) = &heartbeat.fields[0];
+        assert_eq!(name, "custom_mode");
+        assert_eq!(custom_mode.r#type.as_deref(), Some("uint32_t"));
+        let (_, mav_type) = heartbeat
+            .fields
+            .iter()
+            .find(|(name, _)| name == "type")
+            .unwrap();
+        assert_eq!(mav_type.r#enum.as_deref(), Some("MAV_TYPE"));
+        // the option types themselves carry no MAVLink options
+        assert!(!options
+            .iter()
+            .any(|message| message.full_name.starts_with("mav.")));
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "ardupilotmega")]
+mod test_included_enum_option {
+    use proto_mav::proto::mav_options;
+
+    #[test]
+    pub fn test_enum_of_an_include() {
+        let options = mav_options();
+        let mount_configure = options
+            .iter()
+            .find(|message| message.full_name == "ardupilotmega.MOUNT_CONFIGURE")
+            .expect("MOUNT_CONFIGURE not in the descriptor set");
+        let (_, mount_mode) = mount_configure
+            .fields
+            .iter()
+            .find(|(name, _)| name == "mount_mode")
+            .unwrap();
+        // the proto name of the enum, the same as the field type
+        assert_eq!(mount_mode.r#enum.as_deref(), Some("common.MAV_MOUNT_MODE"));
+    }
+}
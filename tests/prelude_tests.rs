@@ -0,0 +1,22 @@
+#[cfg(test)]
+#[cfg(feature = "ardupilotmega")]
+mod test_prelude {
+    use proto_mav::mavlink::ardupilotmega::prelude::*;
+
+    #[test]
+    pub fn test_prelude_covers_includes() {
+        // HEARTBEAT and MAV_TYPE come from common.xml
+        let heartbeat = Heartbeat {
+            r#type: MavType::Quadrotor as i32,
+            ..Default::default()
+        };
+        let msg = MavMessage::from(proto_mav::mavlink::common::MavMessage::Heartbeat(heartbeat));
+        assert_eq!(msg.message_id(), 0);
+        assert_eq!(msg.kind(), MavMessageKind::Heartbeat);
+
+        let msg = MavMessage::Ahrs(Ahrs::default());
+        assert_eq!(msg.message_name(), "Ahrs");
+        let payload = msg.mavlink_ser();
+        assert!(MavMessage::parse(MavlinkVersion::V2, 163, &payload).is_ok());
+    }
+}
@@ -0,0 +1,46 @@
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_proto_sources {
+    #[test]
+    pub fn test_proto_sources() {
+        let sources = proto_mav::proto_sources();
+        let (_, common) = sources
+            .iter()
+            .find(|(name, _)| *name == "common.proto")
+            .expect("common.proto missing");
+        assert!(common.contains("package common;"));
+        assert!(common.contains("message HEARTBEAT {"));
+        // the options every dialect imports
+        assert!(sources.iter().any(|(name, _)| *name == "mav.proto"));
+    }
+
+    #[test]
+    pub fn test_descriptor_set() {
+        use prost::Message;
+
+        let set = prost_types::FileDescriptorSet::decode(proto_mav::proto::DESCRIPTOR_SET)
+            .expect("DESCRIPTOR_SET is not a FileDescriptorSet");
+        let common = set
+            .file
+            .iter()
+            .find(|file| file.name() == "common.proto")
+            .expect("common.proto missing");
+        assert_eq!(common.package(), "common");
+        assert!(common.dependency.iter().any(|dep| dep == "mav.proto"));
+        assert!(common
+            .message_type
+            .iter()
+            .any(|message| message.name() == "HEARTBEAT"));
+        let mav = set
+            .file
+            .iter()
+            .find(|file| file.name() == "mav.proto")
+            .expect("mav.proto missing");
+        assert_eq!(mav.package(), "mav");
+        // imported by mav.proto for the option extensions
+        assert!(set
+            .file
+            .iter()
+            .any(|file| file.name() == "google/protobuf/descriptor.proto"));
+    }
+}
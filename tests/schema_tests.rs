@@ -0,0 +1,35 @@
+#[cfg(test)]
+#[cfg(all(feature = "schema", feature = "common", feature = "ardupilotmega"))]
+mod test_schema {
+    use proto_mav::*;
+
+    #[test]
+    pub fn test_dialect_schema() {
+        let schema: serde_json::Value = serde_json::from_str(mavlink::common::SCHEMA).unwrap();
+        let heartbeat = &schema["definitions"]["HEARTBEAT"];
+        assert_eq!(heartbeat["x-mavlink-id"], 0);
+        assert_eq!(
+            heartbeat["properties"]["custom_mode"]["maximum"],
+            4294967295u32
+        );
+        let gps = &schema["definitions"]["GPS_RAW_INT"]["properties"];
+        assert_eq!(gps["alt"]["x-mavlink-units"], "mm");
+        assert_eq!(
+            gps["fix_type"]["allOf"][0]["$ref"],
+            "#/definitions/GPS_FIX_TYPE"
+        );
+        assert!(schema["definitions"]["GPS_FIX_TYPE"]["oneOf"].is_array());
+    }
+
+    #[test]
+    pub fn test_includes_and_registry() {
+        let schema: serde_json::Value =
+            serde_json::from_str(mavlink::ardupilotmega::SCHEMA).unwrap();
+        // own and included messages
+        assert!(schema["definitions"]["AHRS"].is_object());
+        assert!(schema["definitions"]["HEARTBEAT"].is_object());
+        assert!(mavlink::SCHEMAS
+            .iter()
+            .any(|(name, schema)| *name == "common" && *schema == mavlink::common::SCHEMA));
+    }
+}
@@ -0,0 +1,29 @@
+#[cfg(test)]
+#[cfg(all(feature = "serde", feature = "ardupilotmega"))]
+mod test_serde {
+    use proto_mav::*;
+
+    #[test]
+    pub fn test_tagged_message() {
+        let heartbeat = proto::common::Heartbeat {
+            custom_mode: 5,
+            ..Default::default()
+        };
+        let msg = mavlink::common::MavMessage::Heartbeat(heartbeat.clone());
+        let json = serde_json::to_value(&msg).unwrap();
+        assert_eq!(json["type"], "Heartbeat");
+        assert_eq!(json["message"]["custom_mode"], 5);
+
+        // messages of included dialects are nested under the dialect
+        let msg = mavlink::ardupilotmega::MavMessage::Common(msg);
+        let json = serde_json::to_string(&msg).unwrap();
+        let back: mavlink::ardupilotmega::MavMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, msg);
+    }
+
+    #[test]
+    pub fn test_message_kind() {
+        let kind = mavlink::ardupilotmega::MavMessageKind::Heartbeat;
+        assert_eq!(serde_json::to_string(&kind).unwrap(), "\"Heartbeat\"");
+    }
+}
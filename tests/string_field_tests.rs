@@ -0,0 +1,49 @@
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_string_fields {
+    use proto_mav::*;
+
+    /// PARAM_VALUE.param_id filled with bytes that are not valid UTF-8
+    const RAW_PARAM_ID: [u8; 16] = [
+        b'S', b'N', 0xff, 0xfe, 0xf4, 0x8f, 0xbc, 0x80, 0xc3, 0xa9, 0, 0, 0, 0, 0, 0,
+    ];
+
+    #[test]
+    pub fn test_raw_bytes_accessor() {
+        let mut msg = proto::common::ParamValue::default();
+        msg.set_param_id_bytes(&RAW_PARAM_ID);
+        assert!(msg.param_id.starts_with("SN"));
+        assert_eq!(msg.param_id_bytes(), RAW_PARAM_ID.to_vec());
+    }
+
+    #[test]
+    pub fn test_non_utf8_round_trip() {
+        let mut msg = proto::common::ParamValue::default();
+        msg.set_param_id_bytes(&RAW_PARAM_ID);
+
+        let payload = msg.mavlink_ser();
+        let recv_msg = proto::common::ParamValue::mavlink_deser(MavlinkVersion::V2, &payload)
+            .expect("Failed to parse PARAM_VALUE");
+        assert_eq!(recv_msg.param_id_bytes(), RAW_PARAM_ID.to_vec());
+        assert_eq!(recv_msg.mavlink_ser(), payload);
+    }
+
+    #[test]
+    pub fn test_max_len() {
+        assert_eq!(proto::common::ParamValue::PARAM_ID_MAX_LEN, 16);
+        let mut msg = proto::common::ParamValue {
+            param_id: "SHORT".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(msg.validate(), Ok(()));
+        // short strings are zero padded to the full array
+        let payload = msg.mavlink_ser();
+        assert_eq!(payload.len(), proto::common::ParamValue::ENCODED_LEN);
+
+        msg.param_id = "A_PARAMETER_NAME_TOO_LONG".to_string();
+        let error = msg.validate().unwrap_err();
+        assert_eq!(error.field, "param_id");
+        assert_eq!(error.max_len, 16);
+        assert_eq!(msg.mavlink_ser().len(), payload.len());
+    }
+}
@@ -0,0 +1,43 @@
+//! Needs a crate generated with `[proto] u8_arrays_as_bytes`, run with
+//! `PROTO_MAV_CONFIG=tests/u8_bytes.toml cargo test --features common --test u8_bytes_tests`.
+
+#[cfg(test)]
+#[cfg(all(proto_mav_u8_bytes, feature = "common"))]
+mod test_u8_bytes {
+    use prost::Message as _;
+    use proto_mav::*;
+
+    fn ftp_msg() -> proto::common::FileTransferProtocol {
+        proto::common::FileTransferProtocol {
+            target_network: 1,
+            target_system: 2,
+            target_component: 3,
+            payload: (0..251).map(|i| i as u8).collect(),
+        }
+    }
+
+    #[test]
+    pub fn test_mavlink_round_trip() {
+        let msg = ftp_msg();
+        let payload = msg.mavlink_ser();
+        assert_eq!(payload.len(), 254);
+        assert_eq!(&payload[3..], &msg.payload[..]);
+        for version in [MavlinkVersion::V1, MavlinkVersion::V2] {
+            let recv_msg = proto::common::FileTransferProtocol::mavlink_deser(version, &payload)
+                .expect("Failed to parse FILE_TRANSFER_PROTOCOL");
+            assert_eq!(recv_msg, msg);
+        }
+    }
+
+    #[test]
+    pub fn test_proto_round_trip() {
+        let msg = ftp_msg();
+        let encoded = msg.encode_to_vec();
+        // one length delimited bytes field, not 251 varints
+        assert!(encoded.len() < 2 * 251);
+        let decoded = proto::common::FileTransferProtocol::decode(&encoded[..])
+            .expect("Failed to decode FILE_TRANSFER_PROTOCOL");
+        assert_eq!(decoded, msg);
+        assert_eq!(decoded.mavlink_ser(), msg.mavlink_ser());
+    }
+}
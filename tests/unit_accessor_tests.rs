@@ -0,0 +1,24 @@
+#[cfg(test)]
+#[cfg(all(feature = "common", feature = "emit-units"))]
+mod test_unit_accessors {
+    use proto_mav::*;
+
+    #[test]
+    pub fn test_scaled_fields() {
+        let gps = proto::common::GpsRawInt {
+            time_usec: 2_500_000,
+            lat: 473_977_418,
+            alt: 1_500,
+            vel: 250,
+            ..Default::default()
+        };
+        assert!((gps.lat_deg() - 47.397_741_8).abs() < 1e-9);
+        assert!((gps.alt_m() - 1.5).abs() < 1e-9);
+        assert!((gps.vel_m_s() - 2.5).abs() < 1e-9);
+        assert!((gps.time_s() - 2.5).abs() < 1e-9);
+
+        let info = proto::common::GpsRawInt::INFO;
+        let lat = info.fields.iter().find(|f| f.name == "lat").unwrap();
+        assert_eq!(lat.units, Some("degE7"));
+    }
+}
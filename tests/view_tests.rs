@@ -0,0 +1,31 @@
+mod test_shared;
+
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_views {
+    use proto_mav::*;
+
+    /// HEARTBEAT payload, see HEARTBEAT_V2 in v2_encode_decode_tests
+    pub const HEARTBEAT_PAYLOAD: &'static [u8] =
+        &[0x05, 0x00, 0x00, 0x00, 0x02, 0x03, 0x59, 0x03, 0x03];
+
+    #[test]
+    pub fn test_heartbeat_view() {
+        let view = mavlink::common::HeartbeatView::new(HEARTBEAT_PAYLOAD);
+        assert_eq!(view.custom_mode(), 5);
+        assert_eq!(view.r#type(), proto::common::MavType::Quadrotor as u8);
+        assert_eq!(view.mavlink_version(), 3);
+
+        let msg = view
+            .to_message(MavlinkVersion::V2)
+            .expect("Failed to decode view");
+        assert_eq!(msg, crate::test_shared::get_heartbeat_msg());
+    }
+
+    #[test]
+    pub fn test_truncated_view() {
+        let view = mavlink::common::HeartbeatView::new(&HEARTBEAT_PAYLOAD[..4]);
+        assert_eq!(view.custom_mode(), 5);
+        assert_eq!(view.mavlink_version(), 0);
+    }
+}
@@ -0,0 +1,18 @@
+#[cfg(test)]
+#[cfg(feature = "common")]
+mod test_wip {
+    use proto_mav::mavlink::common::MavMessage;
+    use proto_mav::Message;
+
+    #[test]
+    pub fn test_wip_message() {
+        // LINK_NODE_STATUS is marked <wip/>
+        let id = MavMessage::message_id_from_name("LinkNodeStatus");
+        if cfg!(feature = "emit-wip") {
+            assert_eq!(id, Ok(8));
+        } else {
+            assert!(id.is_err());
+        }
+        assert_eq!(MavMessage::message_id_from_name("Heartbeat"), Ok(0));
+    }
+}